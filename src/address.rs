@@ -0,0 +1,149 @@
+/// Directions as they are written out, with the USPS abbreviation
+const DIRECTIONS: [(&str, &str); 8] = [
+    ("north", "N"),
+    ("south", "S"),
+    ("east", "E"),
+    ("west", "W"),
+    ("northeast", "NE"),
+    ("northwest", "NW"),
+    ("southeast", "SE"),
+    ("southwest", "SW"),
+];
+
+/// Street suffixes, every spelling with the USPS abbreviation first
+const SUFFIXES: [&[&str]; 22] = [
+    &["Aly", "alley"],
+    &["Ave", "av", "avenue"],
+    &["Blvd", "boulevard"],
+    &["Cir", "circle"],
+    &["Ct", "court"],
+    &["Cv", "cove"],
+    &["Dr", "drive"],
+    &["Hwy", "highway"],
+    &["Ln", "lane"],
+    &["Loop"],
+    &["Pkwy", "parkway"],
+    &["Pl", "place"],
+    &["Plz", "plaza"],
+    &["Rd", "road"],
+    &["Row"],
+    &["Sq", "square"],
+    &["St", "street"],
+    &["Ter", "terrace"],
+    &["Trl", "trail"],
+    &["Way"],
+    &["Xing", "crossing"],
+    &["Run"],
+];
+
+/// Words that start the unit part of a street line
+const UNIT_WORDS: [&str; 7] = ["apt", "apartment", "unit", "ste", "suite", "fl", "rm"];
+
+/// An address split into the parts a mailing address is made of. Everything
+/// is as the page wrote it except the directionals and the suffix, which are
+/// abbreviated the way USPS does.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressParts {
+    pub number: String,
+    pub predirectional: Option<String>,
+    pub street: String,
+    pub suffix: Option<String>,
+    pub postdirectional: Option<String>,
+    pub unit: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub zip: Option<String>,
+}
+
+fn direction(word: &str) -> Option<String> {
+    let word = word.trim_end_matches('.').to_lowercase();
+    DIRECTIONS
+        .iter()
+        .find(|(name, abbr)| word == *name || word == abbr.to_lowercase())
+        .map(|(_, abbr)| abbr.to_string())
+}
+
+fn suffix(word: &str) -> Option<String> {
+    let word = word.trim_end_matches('.').to_lowercase();
+    SUFFIXES
+        .iter()
+        .find(|spellings| spellings.iter().any(|s| s.to_lowercase() == word))
+        .map(|spellings| spellings[0].to_string())
+}
+
+/// Splits the street line of an address, e.g. `123 N Main Street Apt 4`.
+/// `None` when it doesn't start with a house number or has no street name
+/// left once the other parts are taken off.
+pub fn parse(
+    street_line: &str,
+    city: Option<&str>,
+    state: Option<&str>,
+    zip: Option<&str>,
+) -> Option<AddressParts> {
+    // Anything after a comma belongs to the city, given separately
+    let line = street_line.split(',').next().unwrap_or(street_line);
+    let mut words = line.split_whitespace().collect::<Vec<&str>>();
+
+    let unit_at = words.iter().position(|word| {
+        word.starts_with('#')
+            || UNIT_WORDS.contains(&word.trim_end_matches('.').to_lowercase().as_str())
+    });
+    let unit = unit_at.and_then(|at| {
+        let mut rest = words.split_off(at);
+        if rest[0].starts_with('#') && rest[0].len() > 1 {
+            rest[0] = &rest[0][1..];
+        } else {
+            rest.remove(0);
+        }
+        (!rest.is_empty()).then(|| rest.join(" ").trim_start_matches('#').to_string())
+    });
+
+    let number = words
+        .first()
+        .filter(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+    let number = number.to_string();
+    let mut words = words.split_off(1);
+
+    let predirectional = match words.first().and_then(|word| direction(word)) {
+        Some(d) if words.len() > 1 => {
+            words.remove(0);
+            Some(d)
+        }
+        _ => None,
+    };
+    let postdirectional = match words.last().and_then(|word| direction(word)) {
+        Some(d) if words.len() > 1 => {
+            words.pop();
+            Some(d)
+        }
+        _ => None,
+    };
+    let suffix = match words.last().and_then(|word| suffix(word)) {
+        Some(s) if words.len() > 1 => {
+            words.pop();
+            Some(s)
+        }
+        _ => None,
+    };
+    if words.is_empty() {
+        return None;
+    }
+
+    let part = |value: Option<&str>| {
+        value
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    Some(AddressParts {
+        number,
+        predirectional,
+        street: words.join(" "),
+        suffix,
+        postdirectional,
+        unit,
+        city: part(city),
+        state: part(state),
+        zip: part(zip),
+    })
+}
@@ -0,0 +1,348 @@
+use std::error::Error;
+use std::fs;
+
+use crate::hash;
+use crate::json;
+use crate::manifest::Manifest;
+
+/// The info.txt lines compared, as field name and line label
+const FIELDS: [(&str, &str); 14] = [
+    ("url", "URL"),
+    ("mls", "MLS number"),
+    ("info", "Info"),
+    ("address", "Address"),
+    ("year_built", "Year built"),
+    ("property_type", "Property type"),
+    ("virtual_tour", "Virtual tour"),
+    ("video", "Video"),
+    ("open_house", "Open house"),
+    ("hoa_fee", "HOA fee"),
+    ("annual_tax", "Annual tax"),
+    ("neighborhood", "Neighborhood"),
+    ("school", "School"),
+    ("images", "Number of images found"),
+];
+
+/// Longest value shown in a table cell, the description is cut to this
+const CELL_WIDTH: usize = 48;
+
+/// The value of every field in a listing's info.txt, `None` when it is missing
+fn read_fields(info_file_path: &str) -> Result<Vec<Option<String>>, Box<dyn Error>> {
+    let text = fs::read_to_string(info_file_path)
+        .map_err(|e| format!("Unable to read {info_file_path}: {e}"))?;
+    Ok(FIELDS
+        .iter()
+        .map(|(_, label)| {
+            text.lines()
+                .find_map(|line| line.strip_prefix(&format!("{label}: ")))
+                .map(str::to_string)
+        })
+        .collect())
+}
+
+/// The info.txt fields of a listing before it is scraped again, for
+/// `field_changes`. `None` the first time round.
+pub fn snapshot(info_file_path: &str) -> Option<Vec<Option<String>>> {
+    read_fields(info_file_path).ok()
+}
+
+/// The fields of `info_file_path` that differ from `before`, as JSON objects
+/// with the field and its old and new values, null when missing
+pub fn field_changes(
+    before: &[Option<String>],
+    info_file_path: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let after = read_fields(info_file_path)?;
+    let value = |v: &Option<String>| v.as_deref().map(json::string).unwrap_or("null".into());
+    Ok(FIELDS
+        .iter()
+        .zip(before.iter().zip(&after))
+        .filter(|(_, (old, new))| old != new)
+        .map(|((field, _), (old, new))| {
+            json::object(&[
+                ("field", json::string(field)),
+                ("old", value(old)),
+                ("new", value(new)),
+            ])
+        })
+        .collect())
+}
+
+fn cell(value: &Option<String>) -> String {
+    match value {
+        Some(value) if value.chars().count() > CELL_WIDTH => {
+            let cut: String = value.chars().take(CELL_WIDTH - 3).collect();
+            format!("{cut}...")
+        }
+        Some(value) => value.clone(),
+        None => "(missing)".to_string(),
+    }
+}
+
+/// Prints the info.txt fields of `<output>/<name>` and `<output>/<other>` side
+/// by side, marking the ones that differ, or as one JSON object
+pub fn compare(output: &str, name: &str, other: &str, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let a = read_fields(&format!("{output}/{name}/info.txt"))?;
+    let b = read_fields(&format!("{output}/{other}/info.txt"))?;
+
+    if as_json {
+        let value = |v: &Option<String>| v.as_deref().map(json::string).unwrap_or("null".into());
+        let fields = FIELDS
+            .iter()
+            .zip(a.iter().zip(&b))
+            .map(|((field, _), (a, b))| {
+                let same = if a == b { "true" } else { "false" };
+                (
+                    *field,
+                    json::object(&[("a", value(a)), ("b", value(b)), ("same", same.to_string())]),
+                )
+            })
+            .collect::<Vec<(&str, String)>>();
+        println!(
+            "{}",
+            json::object(&[
+                ("a", json::string(name)),
+                ("b", json::string(other)),
+                ("fields", json::object(&fields)),
+            ])
+        );
+        return Ok(());
+    }
+
+    let rows = FIELDS
+        .iter()
+        .zip(a.iter().zip(&b))
+        .map(|((field, _), (a, b))| {
+            let marker = if a == b { " " } else { "*" };
+            (format!("{marker} {field}"), cell(a), cell(b))
+        })
+        .collect::<Vec<(String, String, String)>>();
+    let width = |header: &str, column: &dyn Fn(&(String, String, String)) -> usize| {
+        rows.iter()
+            .map(column)
+            .max()
+            .unwrap_or(0)
+            .max(header.chars().count())
+    };
+    let field_width = width("  field", &|r| r.0.chars().count());
+    let a_width = width(name, &|r| r.1.chars().count());
+
+    println!("{:field_width$}  {:a_width$}  {}", "  field", name, other);
+    for (field, a, b) in rows {
+        println!("{field:field_width$}  {a:a_width$}  {b}");
+    }
+
+    Ok(())
+}
+
+/// Content hash and listing-relative file of every image in a listing's
+/// manifest, or its URL when --hash-only kept no file
+fn image_hashes(output: &str, name: &str) -> Result<Vec<(u64, String)>, Box<dyn Error>> {
+    let base_dir = format!("{output}/{name}");
+    let manifest_path = format!("{base_dir}/manifest.txt");
+    let manifest = Manifest::load(&manifest_path)
+        .map_err(|e| format!("Unable to load {manifest_path}: {e}"))?;
+    let mut hashes = Vec::new();
+    for entry in manifest.entries.iter().filter(|e| !e.failed) {
+        if let Ok(hash) = u64::from_str_radix(&entry.hash, 16) {
+            hashes.push((hash, entry.url.clone()));
+        } else if let Ok(bytes) = fs::read(format!("{base_dir}/{}", entry.file)) {
+            hashes.push((hash::fnv1a64(&bytes), entry.file.clone()));
+        }
+    }
+    Ok(hashes)
+}
+
+/// Escapes `text` for an HTML attribute or element
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A page putting the photos only in `name` next to the ones only in `other`
+fn diff_html(
+    output: &str,
+    (name, added): (&str, &[&String]),
+    (other, removed): (&str, &[&String]),
+) -> String {
+    let column = |listing: &str, files: &[&String]| {
+        let images = files
+            .iter()
+            .map(|file| {
+                let src = if file.starts_with("http") {
+                    file.to_string()
+                } else {
+                    fs::canonicalize(format!("{output}/{listing}/{file}"))
+                        .map(|p| format!("file://{}", p.to_string_lossy()))
+                        .unwrap_or_default()
+                };
+                format!(
+                    "<figure><img src=\"{}\"><figcaption>{}</figcaption></figure>",
+                    html_escape(&src),
+                    html_escape(file)
+                )
+            })
+            .collect::<String>();
+        format!(
+            "<div><h2>Only in {}</h2>{images}</div>",
+            html_escape(listing)
+        )
+    };
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{} vs {}</title>\
+         <style>body{{display:flex;gap:2em;font-family:sans-serif}}div{{flex:1}}img{{max-width:100%}}</style>\
+         </head><body>{}{}</body></html>\n",
+        html_escape(name),
+        html_escape(other),
+        column(name, added),
+        column(other, removed)
+    )
+}
+
+/// Prints the images of `<output>/<name>` that `<output>/<other>` doesn't have
+/// and the other way round, matched by content so renumbered photos count as
+/// unchanged. `html` also gets a page showing both sides.
+pub fn compare_images(
+    output: &str,
+    name: &str,
+    other: &str,
+    as_json: bool,
+    html: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let a = image_hashes(output, name)?;
+    let b = image_hashes(output, other)?;
+    let has = |images: &[(u64, String)], hash: u64| images.iter().any(|(h, _)| *h == hash);
+    let added = a
+        .iter()
+        .filter(|(hash, _)| !has(&b, *hash))
+        .map(|(_, file)| file)
+        .collect::<Vec<_>>();
+    let removed = b
+        .iter()
+        .filter(|(hash, _)| !has(&a, *hash))
+        .map(|(_, file)| file)
+        .collect::<Vec<_>>();
+    let unchanged = a.len() - added.len();
+
+    if let Some(html) = html {
+        fs::write(html, diff_html(output, (name, &added), (other, &removed)))
+            .map_err(|e| format!("Unable to write {html}: {e}"))?;
+    }
+
+    if as_json {
+        let files = |files: &[&String]| {
+            json::array(&files.iter().map(|f| json::string(f)).collect::<Vec<_>>())
+        };
+        println!(
+            "{}",
+            json::object(&[
+                ("a", json::string(name)),
+                ("b", json::string(other)),
+                ("added", files(&added)),
+                ("removed", files(&removed)),
+                ("unchanged", unchanged.to_string()),
+            ])
+        );
+        return Ok(());
+    }
+
+    for file in &added {
+        println!("+ {name}/{file}");
+    }
+    for file in &removed {
+        println!("- {other}/{file}");
+    }
+    println!(
+        "{} added, {} removed, {unchanged} unchanged",
+        added.len(),
+        removed.len()
+    );
+    Ok(())
+}
+
+/// Listings under `output` that share at least `min_shared` of their photos,
+/// counted against the smaller of the two, as a JSON report. Each group is
+/// the listings joined by such pairs, likely relists of one property.
+pub fn duplicates(output: &str, min_shared: f64) -> Result<String, Box<dyn Error>> {
+    let mut listings = Vec::new();
+    for dir in fs::read_dir(output)
+        .map_err(|e| format!("Unable to read {output}: {e}"))?
+        .flatten()
+    {
+        let name = dir.file_name().to_string_lossy().to_string();
+        if !dir.path().join("manifest.txt").is_file() {
+            continue;
+        }
+        let mut hashes = image_hashes(output, &name)?
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect::<Vec<u64>>();
+        hashes.sort_unstable();
+        hashes.dedup();
+        if !hashes.is_empty() {
+            listings.push((name, hashes));
+        }
+    }
+    listings.sort();
+
+    // Which group each listing is in, merged as pairs are found
+    let mut group = (0..listings.len()).collect::<Vec<usize>>();
+    let mut pairs = Vec::new();
+    for a in 0..listings.len() {
+        for b in a + 1..listings.len() {
+            let (a_hashes, b_hashes) = (&listings[a].1, &listings[b].1);
+            let shared = a_hashes
+                .iter()
+                .filter(|h| b_hashes.binary_search(h).is_ok())
+                .count();
+            let proportion = shared as f64 / a_hashes.len().min(b_hashes.len()) as f64;
+            if shared > 0 && proportion >= min_shared {
+                let (from, to) = (group[b], group[a]);
+                for g in group.iter_mut().filter(|g| **g == from) {
+                    *g = to;
+                }
+                pairs.push((a, b, shared, proportion));
+            }
+        }
+    }
+
+    let mut groups = Vec::new();
+    let mut roots = group.clone();
+    roots.sort_unstable();
+    roots.dedup();
+    for root in roots {
+        let members = (0..listings.len())
+            .filter(|i| group[*i] == root)
+            .collect::<Vec<usize>>();
+        if members.len() < 2 {
+            continue;
+        }
+        let names = members
+            .iter()
+            .map(|i| json::string(&listings[*i].0))
+            .collect::<Vec<String>>();
+        let group_pairs = pairs
+            .iter()
+            .filter(|(a, ..)| group[*a] == root)
+            .map(|(a, b, shared, proportion)| {
+                json::object(&[
+                    ("a", json::string(&listings[*a].0)),
+                    ("b", json::string(&listings[*b].0)),
+                    ("shared", shared.to_string()),
+                    ("proportion", format!("{proportion:.2}")),
+                ])
+            })
+            .collect::<Vec<String>>();
+        groups.push(json::object(&[
+            ("listings", json::array(&names)),
+            ("pairs", json::array(&group_pairs)),
+        ]));
+    }
+
+    Ok(json::object(&[
+        ("listings", listings.len().to_string()),
+        ("groups", json::array(&groups)),
+    ]))
+}
@@ -0,0 +1,278 @@
+use std::error::Error;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::httpdate;
+
+#[derive(Debug, Clone)]
+struct Cookie {
+    domain: String,
+    include_subdomains: bool,
+    path: String,
+    secure: bool,
+    /// Seconds since the epoch, 0 for a session cookie
+    expires: u64,
+    name: String,
+    value: String,
+}
+
+/// Cookies kept in a Netscape `cookies.txt` file, the format curl reads and
+/// writes with `-b`/`-c`, so the page fetch and image downloads share one jar.
+///
+/// The file holds live session cookies in plain text. Anyone who can read it
+/// can act as you on the sites it has cookies for, keep it somewhere private.
+#[derive(Debug)]
+pub struct CookieJar {
+    path: String,
+    cookies: Vec<Cookie>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Splits `https://host:port/path?query` into the host and path
+fn host_and_path(url: &str) -> (String, String) {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let host = host.rsplit('@').next().unwrap_or(host);
+    let host = host.split(':').next().unwrap_or(host);
+    let path = path.split(['?', '#']).next().unwrap_or("/");
+    (host.to_lowercase(), path.to_string())
+}
+
+impl CookieJar {
+    /// Opens the jar at `path`, starting empty when the file doesn't exist yet
+    pub fn load(path: &str) -> Result<CookieJar, Box<dyn Error>> {
+        let mut jar = CookieJar {
+            path: path.to_string(),
+            cookies: Vec::new(),
+        };
+        if !std::path::Path::new(path).exists() {
+            return Ok(jar);
+        }
+
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                continue;
+            }
+            jar.cookies.push(Cookie {
+                domain: fields[0].trim_start_matches('.').to_lowercase(),
+                include_subdomains: fields[1] == "TRUE",
+                path: fields[2].to_string(),
+                secure: fields[3] == "TRUE",
+                expires: fields[4].parse().unwrap_or(0),
+                name: fields[5].to_string(),
+                value: fields[6].to_string(),
+            });
+        }
+
+        Ok(jar)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let mut text = String::from("# Netscape HTTP Cookie File\n");
+        for c in &self.cookies {
+            let domain = if c.include_subdomains {
+                format!(".{}", c.domain)
+            } else {
+                c.domain.clone()
+            };
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                domain,
+                if c.include_subdomains {
+                    "TRUE"
+                } else {
+                    "FALSE"
+                },
+                c.path,
+                if c.secure { "TRUE" } else { "FALSE" },
+                c.expires,
+                c.name,
+                c.value
+            ));
+        }
+        fs::write(&self.path, text)?;
+        Ok(())
+    }
+
+    /// Copies the cookies for `url`'s site from a Firefox profile's
+    /// cookies.sqlite into the jar, returning how many there were. Reads the
+    /// database with the sqlite3 tool, opened immutable so a running Firefox
+    /// doesn't lock it out. Chrome encrypts its cookies and isn't supported.
+    #[cfg(feature = "browser-cookies")]
+    pub fn import_firefox(&mut self, profile: &str, url: &str) -> Result<usize, Box<dyn Error>> {
+        let database = format!("{profile}/cookies.sqlite");
+        if !std::path::Path::new(&database).exists() {
+            return Err(format!("No cookies.sqlite in {profile}").into());
+        }
+        let (host, _) = host_and_path(url);
+        let site = host.strip_prefix("www.").unwrap_or(&host);
+        if !site
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        {
+            return Err(format!("Unexpected host {host}").into());
+        }
+
+        const SEPARATOR: &str = "\u{1f}";
+        let output = std::process::Command::new("sqlite3")
+            .arg("-separator")
+            .arg(SEPARATOR)
+            .arg(format!("file:{database}?immutable=1"))
+            .arg(format!(
+                "SELECT host, path, isSecure, expiry, name, value FROM moz_cookies \
+                 WHERE host = '{site}' OR host LIKE '%.{site}'"
+            ))
+            .output()
+            .map_err(|e| format!("Unable to run sqlite3: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "sqlite3 exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+
+        let mut imported = 0;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let fields: Vec<&str> = line.split(SEPARATOR).collect();
+            let [domain, path, secure, expires, name, value] = fields[..] else {
+                continue;
+            };
+            let cookie = Cookie {
+                domain: domain.trim_start_matches('.').to_lowercase(),
+                include_subdomains: domain.starts_with('.'),
+                path: path.to_string(),
+                secure: secure == "1",
+                // Firefox has used milliseconds in some versions
+                expires: expires
+                    .parse::<u64>()
+                    .map(|e| if e > 1 << 40 { e / 1000 } else { e })
+                    .unwrap_or(0),
+                name: name.to_string(),
+                value: value.to_string(),
+            };
+            self.cookies.retain(|c| {
+                !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+            });
+            self.cookies.push(cookie);
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// The `Cookie` header value to send with a request to `url`
+    pub fn header_for(&self, url: &str) -> Option<String> {
+        let (host, path) = host_and_path(url);
+        let secure = url.starts_with("https://");
+        let now = now();
+
+        let pairs = self
+            .cookies
+            .iter()
+            .filter(|c| {
+                let domain_matches = host == c.domain
+                    || (c.include_subdomains && host.ends_with(&format!(".{}", c.domain)));
+                domain_matches
+                    && path.starts_with(&c.path)
+                    && (secure || !c.secure)
+                    && (c.expires == 0 || c.expires > now)
+            })
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect::<Vec<String>>();
+
+        if pairs.is_empty() {
+            None
+        } else {
+            Some(pairs.join("; "))
+        }
+    }
+
+    /// Records the `Set-Cookie` headers of a response to `url`
+    pub fn store_response(&mut self, url: &str, headers: &reqwest::header::HeaderMap) {
+        let (host, _) = host_and_path(url);
+        for header in headers.get_all(reqwest::header::SET_COOKIE) {
+            if let Ok(header) = header.to_str() {
+                self.store(&host, header);
+            }
+        }
+    }
+
+    fn store(&mut self, host: &str, set_cookie: &str) {
+        let mut parts = set_cookie.split(';');
+        let Some((name, value)) = parts.next().and_then(|p| p.split_once('=')) else {
+            return;
+        };
+        let mut cookie = Cookie {
+            domain: host.to_string(),
+            include_subdomains: false,
+            path: "/".to_string(),
+            secure: false,
+            expires: 0,
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+        };
+
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, val) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let val = val.trim();
+            match key.trim().to_lowercase().as_str() {
+                "domain" if !val.is_empty() => {
+                    cookie.domain = val.trim_start_matches('.').to_lowercase();
+                    cookie.include_subdomains = true;
+                }
+                "path" if val.starts_with('/') => cookie.path = val.to_string(),
+                "secure" => cookie.secure = true,
+                "max-age" => max_age = val.parse::<i64>().ok(),
+                "expires" => {
+                    if let Some(expires) = httpdate::parse(val) {
+                        cookie.expires = expires
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or(1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Max-Age wins over Expires, zero or negative deletes the cookie
+        if let Some(max_age) = max_age {
+            cookie.expires = if max_age > 0 {
+                now() + max_age as u64
+            } else {
+                1
+            };
+        }
+
+        // Don't accept cookies for unrelated domains
+        if host != cookie.domain && !host.ends_with(&format!(".{}", cookie.domain)) {
+            return;
+        }
+
+        self.cookies.retain(|c| {
+            !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+        });
+        if cookie.expires == 0 || cookie.expires > now() {
+            self.cookies.push(cookie);
+        }
+    }
+}
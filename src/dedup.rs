@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+
+use crate::manifest::{self, Manifest};
+use crate::{hash, json};
+
+/// Content hashes of every image under `--output`, kept in
+/// `<output>/content-index.txt` as `hash<TAB>file` lines with the file
+/// relative to the output directory. Built by scanning the listings the first
+/// time, then appended to as images are downloaded.
+#[derive(Debug)]
+pub struct ContentIndex {
+    output: String,
+    files: HashMap<u64, Vec<String>>,
+}
+
+impl ContentIndex {
+    pub fn load(output: &str) -> Result<ContentIndex, Box<dyn Error>> {
+        let mut index = ContentIndex {
+            output: output.to_string(),
+            files: HashMap::new(),
+        };
+
+        let index_path = index.index_path();
+        if std::path::Path::new(&index_path).exists() {
+            for line in fs::read_to_string(&index_path)?.lines() {
+                if let Some((hash, file)) = line.split_once('\t') {
+                    if let Ok(hash) = u64::from_str_radix(hash, 16) {
+                        index.files.entry(hash).or_default().push(file.to_string());
+                    }
+                }
+            }
+            return Ok(index);
+        }
+
+        for listing in fs::read_dir(output)?.flatten() {
+            let name = listing.file_name().to_string_lossy().to_string();
+            let Ok(images) = fs::read_dir(listing.path().join("images")) else {
+                continue;
+            };
+            for image in images.flatten() {
+                let file = format!("{}/images/{}", name, image.file_name().to_string_lossy());
+                if let Ok(bytes) = fs::read(image.path()) {
+                    index.add(hash::fnv1a64(&bytes), &file)?;
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Scans every listing again for a fresh index, after images were moved
+    /// or deleted by hand. Returns the number of images indexed.
+    pub fn rebuild(output: &str) -> Result<usize, Box<dyn Error>> {
+        let index_path = format!("{output}/content-index.txt");
+        if std::path::Path::new(&index_path).exists() {
+            fs::remove_file(&index_path)?;
+        }
+        let index = ContentIndex::load(output)?;
+        Ok(index.files.values().map(Vec::len).sum())
+    }
+
+    fn index_path(&self) -> String {
+        format!("{}/content-index.txt", self.output)
+    }
+
+    fn add(&mut self, hash: u64, file: &str) -> Result<(), Box<dyn Error>> {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.index_path())?
+            .write_all(format!("{hash:016x}\t{file}\n").as_bytes())?;
+        self.files.entry(hash).or_default().push(file.to_string());
+        Ok(())
+    }
+
+    /// Replaces `file` (relative to the output directory) with a hard link to
+    /// an identical image already in the archive and returns that image, or
+    /// records `file` as the first copy of its content
+    pub fn dedupe(&mut self, file: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let path = format!("{}/{}", self.output, file);
+        let bytes = fs::read(&path)?;
+        let hash = hash::fnv1a64(&bytes);
+
+        let original = self.files.get(&hash).and_then(|files| {
+            files.iter().find(|other| {
+                // Compare the bytes too, the index may be stale or the hash collide
+                other.as_str() != file
+                    && fs::read(format!("{}/{}", self.output, other)).is_ok_and(|b| b == bytes)
+            })
+        });
+        match original.cloned() {
+            Some(original) => {
+                // Link next to the file and rename over it, so a failed link
+                // leaves the downloaded copy in place
+                let link = format!("{path}.link");
+                fs::hard_link(format!("{}/{}", self.output, original), &link)?;
+                fs::rename(&link, &path)?;
+                Ok(Some(original))
+            }
+            None => {
+                self.add(hash, file)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// A link without its extension and query, an avif variant of a photo shares
+/// it with the jpeg or webp one
+fn photo_stem(link: &str) -> (&str, &str) {
+    let path = link.split('?').next().unwrap_or(link);
+    let file_start = path.rfind('/').map_or(0, |i| i + 1);
+    match path[file_start..].rsplit_once('.') {
+        Some((stem, extension)) => (&path[..file_start + stem.len()], extension),
+        None => (path, ""),
+    }
+}
+
+/// What happened to the duplicates among the `found` image links of a
+/// listing, as JSON. Each group is a stored image with the other URLs that
+/// came down to it, skipped as the same image or as its avif variant, and
+/// the file in another listing it was hard linked to. Bytes saved count the
+/// downloads skipped and the copies linked, at the stored file's size.
+pub fn report(found: &[String], manifest: &Manifest, base_dir: &str, ignore_query: bool) -> String {
+    let mut entries = manifest
+        .entries
+        .iter()
+        .filter(|e| !e.failed)
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.index);
+
+    let mut groups = Vec::new();
+    let (mut skipped_total, mut linked_total, mut bytes_total) = (0, 0, 0);
+    for entry in entries {
+        let mut skipped: Vec<&String> = Vec::new();
+        for link in found {
+            let (stem, extension) = photo_stem(link);
+            let same = manifest::same_image(link, &entry.url, ignore_query)
+                || (extension.eq_ignore_ascii_case("avif") && stem == photo_stem(&entry.url).0);
+            if same && link != &entry.url && !skipped.contains(&link) {
+                skipped.push(link);
+            }
+        }
+        if skipped.is_empty() && entry.same_as.is_empty() {
+            continue;
+        }
+
+        let size = fs::metadata(format!("{base_dir}/{}", entry.file)).map_or(0, |m| m.len());
+        let copies = skipped.len() as u64 + u64::from(!entry.same_as.is_empty());
+        skipped_total += skipped.len();
+        linked_total += usize::from(!entry.same_as.is_empty());
+        bytes_total += size * copies;
+        let linked_to = if entry.same_as.is_empty() {
+            "null".to_string()
+        } else {
+            json::string(&entry.same_as)
+        };
+        groups.push(json::object(&[
+            ("kept_file", json::string(&entry.file)),
+            ("kept_url", json::string(&entry.url)),
+            (
+                "skipped_urls",
+                json::array(&skipped.iter().map(|u| json::string(u)).collect::<Vec<_>>()),
+            ),
+            ("linked_to", linked_to),
+            ("bytes_saved", (size * copies).to_string()),
+        ]));
+    }
+
+    json::object(&[
+        ("groups", json::array(&groups)),
+        ("skipped_urls", skipped_total.to_string()),
+        ("linked_files", linked_total.to_string()),
+        ("bytes_saved", bytes_total.to_string()),
+    ])
+}
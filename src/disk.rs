@@ -0,0 +1,17 @@
+/// Bytes free for an unprivileged user on the volume holding `path`, `None`
+/// when that can't be told
+#[cfg(unix)]
+pub fn free_bytes(path: &str) -> Option<u64> {
+    let path = std::ffi::CString::new(path).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_bytes(_path: &str) -> Option<u64> {
+    None
+}
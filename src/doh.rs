@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::json::{self, Value};
+
+/// How long a lookup may take before the resolver is taken as unreachable
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// DNS record types in the answers, CNAMEs and the like are skipped
+const TYPE_A: f64 = 1.0;
+const TYPE_AAAA: f64 = 28.0;
+
+/// Parses `--doh`, the URL of a resolver speaking the JSON flavor of
+/// DNS-over-HTTPS, e.g. https://cloudflare-dns.com/dns-query
+pub fn parse_url(url: &str) -> Result<String, String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err("expected a URL like https://1.1.1.1/dns-query".to_string());
+    };
+    if !scheme.eq_ignore_ascii_case("https") && !scheme.eq_ignore_ascii_case("http") {
+        return Err(format!("expected an https URL, not {scheme}"));
+    }
+    if rest
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .is_empty()
+    {
+        return Err("the URL has no host".to_string());
+    }
+    if url.contains('#') {
+        return Err("the URL can't have a fragment".to_string());
+    }
+    Ok(url.to_string())
+}
+
+/// Looks `host` up through the resolver at `resolver`, an IPv6 address when
+/// `ipv6` and IPv4 otherwise. `Ok(None)` when the name has no such address.
+/// The resolver's own host is looked up the usual way, give it as an IP to
+/// avoid that.
+pub fn lookup(resolver: &str, host: &str, ipv6: bool) -> Result<Option<IpAddr>, Box<dyn Error>> {
+    let separator = if resolver.contains('?') { '&' } else { '?' };
+    let record = if ipv6 { "AAAA" } else { "A" };
+    let url = format!("{resolver}{separator}name={host}&type={record}");
+    let resp = reqwest::blocking::ClientBuilder::new()
+        .timeout(TIMEOUT)
+        .build()?
+        .get(&url)
+        .header(reqwest::header::ACCEPT, "application/dns-json")
+        .send()?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("{resolver} answered {status}").into());
+    }
+    let answer = json::parse(&resp.text()?)
+        .map_err(|e| format!("{resolver} didn't answer with DNS JSON: {e}"))?;
+    // 3 is NXDOMAIN, anything else but 0 is the resolver failing
+    match answer.get("Status") {
+        Some(Value::Number(status)) if *status == 0.0 || *status == 3.0 => {}
+        Some(Value::Number(status)) => {
+            return Err(format!("{resolver} failed to look {host} up, DNS status {status}").into())
+        }
+        _ => return Err(format!("{resolver} didn't answer with DNS JSON").into()),
+    }
+    let wanted = if ipv6 { TYPE_AAAA } else { TYPE_A };
+    let Some(Value::Array(records)) = answer.get("Answer") else {
+        return Ok(None);
+    };
+    Ok(records
+        .iter()
+        .find_map(|record| match (record.get("type"), record.get("data")) {
+            (Some(Value::Number(kind)), Some(Value::String(data))) if *kind == wanted => {
+                data.parse().ok()
+            }
+            _ => None,
+        }))
+}
@@ -0,0 +1,123 @@
+use std::io::Read;
+
+/// File extension for the bytes at the start of a file
+pub fn from_magic(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if head.starts_with(b"\x89PNG") {
+        Some("png")
+    } else if head.starts_with(b"GIF8") {
+        Some("gif")
+    } else if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        Some("webp")
+    } else if head.len() >= 12 && &head[4..8] == b"ftyp" && &head[8..12] == b"avif" {
+        Some("avif")
+    } else {
+        None
+    }
+}
+
+/// File extension for a `Content-Type` header value
+pub fn from_content_type(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim().to_lowercase();
+    match mime.as_str() {
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/avif" => Some("avif"),
+        "image/svg+xml" => Some("svg"),
+        _ => None,
+    }
+}
+
+/// What a downloaded file really is, trusting its bytes over the server
+pub fn detect(path: &str, content_type: &str) -> Option<&'static str> {
+    let mut head = [0u8; 16];
+    let read = std::fs::File::open(path)
+        .and_then(|mut file| file.read(&mut head))
+        .unwrap_or(0);
+    from_magic(&head[..read]).or_else(|| from_content_type(content_type))
+}
+
+/// What is wrong with the structure of a saved image, `None` when it holds
+/// together or isn't a format that can be walked. Follows the chunks of a
+/// PNG, WebP or AVIF to their end and looks for the trailer of a JPEG or GIF,
+/// which catches a download cut short. The pixels aren't decoded.
+pub fn damage(bytes: &[u8]) -> Option<String> {
+    match from_magic(bytes)? {
+        "jpg" => {
+            // Some cameras pad the file after the end of image marker
+            let end = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+            (!bytes[..end].ends_with(&[0xff, 0xd9])).then(|| "no end of image marker".to_string())
+        }
+        "gif" => (bytes.last() != Some(&0x3b)).then(|| "no trailer".to_string()),
+        "png" => png_damage(bytes),
+        "webp" => webp_damage(bytes),
+        "avif" => avif_damage(bytes),
+        _ => None,
+    }
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as u64)
+}
+
+fn le32(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as u64)
+}
+
+/// Length, type, data and CRC chunks after the signature, up to IEND
+fn png_damage(bytes: &[u8]) -> Option<String> {
+    let mut at = 8;
+    while let Some(length) = be32(bytes, at) {
+        let Some(chunk) = bytes.get(at + 4..at + 8) else {
+            break;
+        };
+        at += 12 + length as usize;
+        if at > bytes.len() {
+            return Some(format!(
+                "{} chunk cut short",
+                String::from_utf8_lossy(chunk)
+            ));
+        }
+        if chunk == b"IEND" {
+            return None;
+        }
+    }
+    Some("no IEND chunk".to_string())
+}
+
+/// The RIFF header gives the size of the rest of the file
+fn webp_damage(bytes: &[u8]) -> Option<String> {
+    let expected = le32(bytes, 4)? + 8;
+    let actual = bytes.len() as u64;
+    (actual < expected).then(|| format!("{actual} of {expected} bytes"))
+}
+
+/// ISO boxes one after the other, each starting with its size, fill the file
+fn avif_damage(bytes: &[u8]) -> Option<String> {
+    let mut at = 0u64;
+    let len = bytes.len() as u64;
+    while at + 8 <= len {
+        let size = match be32(bytes, at as usize)? {
+            // The box runs to the end of the file
+            0 => return None,
+            1 => u64::from_be_bytes(
+                bytes
+                    .get(at as usize + 8..at as usize + 16)?
+                    .try_into()
+                    .ok()?,
+            ),
+            size => size,
+        };
+        if size < 8 {
+            return Some(format!("box of {size} bytes at {at}"));
+        }
+        if at + size > len {
+            return Some(format!("box at {at} needs {size} bytes, {} left", len - at));
+        }
+        at += size;
+    }
+    (at != len).then(|| format!("{} stray bytes at the end", len - at))
+}
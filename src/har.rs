@@ -0,0 +1,277 @@
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::{httpdate, json, logging, timing};
+
+/// Where `--har` writes to and whether it keeps the bodies of text responses
+static RECORDER: Mutex<Option<(String, bool)>> = Mutex::new(None);
+static ENTRIES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// Headers whose values are left out, the file is meant to be passed around
+const SECRET_HEADERS: [&str; 2] = ["authorization", "proxy-authorization"];
+
+/// One request with its response
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    pub started: Option<SystemTime>,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub response_headers: Vec<(String, String)>,
+    pub size: Option<u64>,
+    pub body: Option<String>,
+    /// Milliseconds spent looking the host up and connecting, when known
+    pub dns: Option<f64>,
+    pub connect: Option<f64>,
+    /// Milliseconds until the response started and then until it was in
+    pub wait: f64,
+    pub receive: f64,
+}
+
+/// Records every request from now on to the HAR file at `path`, with the
+/// bodies of text responses when `bodies`
+pub fn enable(path: &str, bodies: bool) {
+    *RECORDER.lock().unwrap() = Some((path.to_string(), bodies));
+}
+
+pub fn recording() -> bool {
+    RECORDER.lock().unwrap().is_some()
+}
+
+/// Adds `entry` and writes the file again, so it is complete whenever the
+/// run stops
+pub fn record(entry: Entry) {
+    if !recording() {
+        return;
+    }
+    ENTRIES.lock().unwrap().push(entry);
+    save();
+}
+
+/// Fills in the size and, when keeping bodies, the body of the last request
+/// to `url` once its body has been read
+pub fn finish(url: &str, body: &str) {
+    let Some((_, bodies)) = RECORDER.lock().unwrap().clone() else {
+        return;
+    };
+    if let Some(entry) = ENTRIES
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .rev()
+        .find(|e| e.url == url)
+    {
+        entry.size = Some(body.len() as u64);
+        if bodies {
+            entry.body = Some(body.to_string());
+        }
+    }
+    save();
+}
+
+/// Records a curl request from what `-v` wrote, keeping the last hop of a
+/// redirect
+pub fn record_curl(url: &str, started: SystemTime, elapsed: Duration, verbose: &str) {
+    let mut entry = Entry {
+        started: Some(started),
+        url: url.to_string(),
+        wait: elapsed.as_secs_f64() * 1000.0,
+        ..Entry::default()
+    };
+    for line in verbose.lines().map(|l| l.trim_end_matches('\r')) {
+        if let Some(sent) = line.strip_prefix("> ") {
+            match sent.split_once(": ") {
+                Some((name, value)) => entry
+                    .request_headers
+                    .push((name.to_string(), value.to_string())),
+                None if !sent.is_empty() => {
+                    // A new request line, e.g. after a redirect
+                    let mut parts = sent.split_whitespace();
+                    entry.method = parts.next().unwrap_or("GET").to_string();
+                    entry.http_version = parts.nth(1).unwrap_or_default().to_string();
+                    entry.request_headers.clear();
+                    entry.response_headers.clear();
+                }
+                None => {}
+            }
+        } else if let Some(received) = line.strip_prefix("< ") {
+            if received.starts_with("HTTP/") {
+                let mut parts = received.splitn(3, ' ');
+                entry.http_version = parts.next().unwrap_or_default().to_string();
+                entry.status = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+                entry.status_text = parts.next().unwrap_or_default().trim().to_string();
+            } else if let Some((name, value)) = received.split_once(": ") {
+                entry
+                    .response_headers
+                    .push((name.to_string(), value.to_string()));
+            }
+        }
+    }
+    entry.size = header(&entry.response_headers, "content-length").and_then(|l| l.parse().ok());
+    record(entry);
+}
+
+/// Replaces the wall clock time of the last request to `url` with the
+/// breakdown curl wrote for `timing::CURL_FORMAT`
+pub fn curl_timings(url: &str, line: &str) {
+    let Some([dns, connect, first_byte, total]) = timing::parse(line) else {
+        return;
+    };
+    if !recording() {
+        return;
+    }
+    if let Some(entry) = ENTRIES
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .rev()
+        .find(|e| e.url == url)
+    {
+        entry.dns = Some(dns * 1000.0);
+        entry.connect = Some((connect - dns) * 1000.0);
+        entry.wait = (first_byte - connect) * 1000.0;
+        entry.receive = (total - first_byte) * 1000.0;
+    }
+    save();
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn headers_json(headers: &[(String, String)]) -> String {
+    let objects = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+                "REDACTED"
+            } else {
+                value
+            };
+            json::object(&[("name", json::string(name)), ("value", json::string(value))])
+        })
+        .collect::<Vec<String>>();
+    json::array(&objects)
+}
+
+fn entry_json(entry: &Entry) -> String {
+    let ms = |ms: f64| format!("{:.3}", ms.max(0.0));
+    let known = |value: Option<f64>| value.map_or("-1".to_string(), ms);
+    let total =
+        entry.dns.unwrap_or(0.0) + entry.connect.unwrap_or(0.0) + entry.wait + entry.receive;
+    let version = if entry.http_version.is_empty() {
+        "HTTP/1.1"
+    } else {
+        &entry.http_version
+    };
+    let mut content = vec![
+        (
+            "size",
+            entry.size.map_or("-1".to_string(), |s| s.to_string()),
+        ),
+        (
+            "mimeType",
+            json::string(header(&entry.response_headers, "content-type").unwrap_or_default()),
+        ),
+    ];
+    if let Some(body) = &entry.body {
+        content.push(("text", json::string(body)));
+    }
+    json::object(&[
+        (
+            "startedDateTime",
+            json::string(&httpdate::format_iso(
+                entry.started.unwrap_or(SystemTime::UNIX_EPOCH),
+            )),
+        ),
+        ("time", ms(total)),
+        (
+            "request",
+            json::object(&[
+                (
+                    "method",
+                    json::string(if entry.method.is_empty() {
+                        "GET"
+                    } else {
+                        &entry.method
+                    }),
+                ),
+                ("url", json::string(&entry.url)),
+                ("httpVersion", json::string(version)),
+                ("cookies", "[]".to_string()),
+                ("headers", headers_json(&entry.request_headers)),
+                ("queryString", "[]".to_string()),
+                ("headersSize", "-1".to_string()),
+                ("bodySize", "0".to_string()),
+            ]),
+        ),
+        (
+            "response",
+            json::object(&[
+                ("status", entry.status.to_string()),
+                ("statusText", json::string(&entry.status_text)),
+                ("httpVersion", json::string(version)),
+                ("cookies", "[]".to_string()),
+                ("headers", headers_json(&entry.response_headers)),
+                ("content", json::object(&content)),
+                (
+                    "redirectURL",
+                    json::string(header(&entry.response_headers, "location").unwrap_or_default()),
+                ),
+                ("headersSize", "-1".to_string()),
+                ("bodySize", "-1".to_string()),
+            ]),
+        ),
+        ("cache", "{}".to_string()),
+        (
+            "timings",
+            json::object(&[
+                ("dns", known(entry.dns)),
+                ("connect", known(entry.connect)),
+                ("send", "0".to_string()),
+                ("wait", ms(entry.wait)),
+                ("receive", ms(entry.receive)),
+            ]),
+        ),
+    ])
+}
+
+fn save() {
+    let Some((path, _)) = RECORDER.lock().unwrap().clone() else {
+        return;
+    };
+    let entries = ENTRIES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(entry_json)
+        .collect::<Vec<String>>();
+    let har = json::object(&[(
+        "log",
+        json::object(&[
+            ("version", json::string("1.2")),
+            (
+                "creator",
+                json::object(&[
+                    ("name", json::string(env!("CARGO_PKG_NAME"))),
+                    ("version", json::string(env!("CARGO_PKG_VERSION"))),
+                ]),
+            ),
+            ("entries", json::array(&entries)),
+        ]),
+    )]);
+    if let Err(e) = fs::write(&path, har + "\n") {
+        logging::warn(
+            "har",
+            &format!("Unable to write {path}: {e}"),
+            &[("path", &path), ("error", &e)],
+        );
+    }
+}
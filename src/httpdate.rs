@@ -0,0 +1,121 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Parses the dates servers send in headers, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+/// Also accepts the dashed `06-Nov-1994` form still common in cookie expiry.
+pub fn parse(date: &str) -> Option<SystemTime> {
+    let date = date.replace('-', " ");
+    let mut parts = date.split_whitespace();
+
+    // The weekday is redundant
+    let first = parts.next()?;
+    let day: u64 = match first.trim_end_matches(',').parse() {
+        Ok(day) => day,
+        Err(_) => parts.next()?.parse().ok()?,
+    };
+    let month = parts.next()?.to_lowercase();
+    let month = MONTHS.iter().position(|m| month.starts_with(m))? as u64 + 1;
+    let mut year: u64 = parts.next()?.parse().ok()?;
+    if year < 100 {
+        year += if year < 70 { 2000 } else { 1900 };
+    }
+
+    let mut time = parts.next()?.split(':').map(|p| p.parse::<u64>());
+    let hour = time.next()?.ok()?;
+    let minute = time.next()?.ok()?;
+    let second = time.next()?.ok()?;
+    if day == 0 || day > 31 || hour > 23 || minute > 59 || second > 60 || year < 1970 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Parses the ISO 8601 dates pages carry in JSON-LD, `2024-05-04` or
+/// `2024-05-04T13:00:00-07:00`, at UTC since the offset is dropped
+pub fn parse_iso(date: &str) -> Option<SystemTime> {
+    let date = date.trim();
+    let mut ymd = date.get(..10)?.split('-').map(|p| p.parse::<u64>());
+    let year = ymd.next()?.ok()?;
+    let month = ymd.next()?.ok()?;
+    let day = ymd.next()?.ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+
+    let mut time = date
+        .get(11..19)
+        .unwrap_or("")
+        .split(':')
+        .map(|p| p.parse::<u64>());
+    let (hour, minute, second) = match (time.next(), time.next(), time.next()) {
+        (Some(Ok(h)), Some(Ok(m)), Some(Ok(s))) if h < 24 && m < 60 && s <= 60 => (h, m, s),
+        _ => (0, 0, 0),
+    };
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// `time` in ISO 8601 at UTC with milliseconds, `2024-05-04T13:00:00.000Z`
+pub fn format_iso(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (year, month, day) = civil_from_days(secs / 86400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+        since.subsec_millis()
+    )
+}
+
+/// The date `days` after 1970-01-01, the inverse of `days_from_civil`
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// How long a `Retry-After` header asks to wait, it is either a number of
+/// seconds or the date to come back at
+pub fn retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = parse(value)?;
+    Some(
+        at.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fs;
+
+use regex::Regex;
+
+/// Name of the file with the URLs to leave alone, read from the working
+/// directory and from `--output`
+pub const FILE_NAME: &str = ".casteelignore";
+
+/// URL patterns from `.casteelignore` files. Each line is a glob matched
+/// against the whole URL, `*` standing for any run of characters, or a regex
+/// after `re:`. Blank lines and lines starting with `#` are skipped.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Regex>,
+}
+
+fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let pattern = glob
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+    Regex::new(&format!("^{pattern}$"))
+}
+
+impl IgnoreRules {
+    /// Rules from every file in `dirs` that exists
+    pub fn load(dirs: &[&str]) -> Result<IgnoreRules, Box<dyn Error>> {
+        let mut rules = IgnoreRules::default();
+        for dir in dirs {
+            let path = format!("{dir}/{FILE_NAME}");
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            for (n, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let pattern = match line.strip_prefix("re:") {
+                    Some(re) => Regex::new(re.trim()),
+                    None => glob_to_regex(line),
+                };
+                rules
+                    .patterns
+                    .push(pattern.map_err(|e| format!("{path} line {}: {e}", n + 1))?);
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        self.patterns.iter().any(|re| re.is_match(url))
+    }
+}
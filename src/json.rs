@@ -0,0 +1,362 @@
+/// Quotes and escapes `text` as a JSON string
+pub fn string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A JSON object from already encoded values
+pub fn object(fields: &[(&str, String)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", string(key), value))
+        .collect::<Vec<String>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// A JSON array from already encoded values
+pub fn array(values: &[String]) -> String {
+    format!("[{}]", values.join(","))
+}
+
+/// A parsed JSON value, object members in the order they were written
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The member `key` of an object
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// The name JSON Schema's "type" uses for it, "integer" for whole numbers
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.fract() == 0.0 => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+}
+
+/// Checks that `text` is one JSON value and returns it without the whitespace
+/// between tokens, so it fits on a JSON line
+pub fn compact(text: &str) -> Result<String, String> {
+    run(text).map(|(_, out)| out)
+}
+
+/// Parses `text`, which must be one JSON value
+pub fn parse(text: &str) -> Result<Value, String> {
+    run(text).map(|(value, _)| value)
+}
+
+/// Deepest nesting of objects and arrays accepted, well short of running
+/// out of stack on deeply nested input like an --enrich-url answer
+const MAX_DEPTH: usize = 128;
+
+fn run(text: &str) -> Result<(Value, String), String> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        pos: 0,
+        depth: 0,
+        out: String::with_capacity(text.len()),
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    Ok((value, parser.out))
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Objects and arrays the parser is inside of
+    depth: usize,
+    out: String,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{message} at byte {}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// Copies the next byte, which must be `expected`
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) != Some(&expected) {
+            return Err(self.error(&format!("expected '{}'", expected as char)));
+        }
+        self.out.push(expected as char);
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.sequence(b'{', b'}', true),
+            Some(b'[') => self.sequence(b'[', b']', false),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    /// An object when `keyed`, otherwise an array
+    fn sequence(&mut self, open: u8, close: u8, keyed: bool) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(&format!("nested more than {MAX_DEPTH} deep")));
+        }
+        self.depth += 1;
+        let value = self.members(open, close, keyed);
+        self.depth -= 1;
+        value
+    }
+
+    fn members(&mut self, open: u8, close: u8, keyed: bool) -> Result<Value, String> {
+        let mut members = Vec::new();
+        let done = |members: Vec<(String, Value)>| {
+            if keyed {
+                Value::Object(members)
+            } else {
+                Value::Array(members.into_iter().map(|(_, v)| v).collect())
+            }
+        };
+        self.expect(open)?;
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&close) {
+            self.expect(close)?;
+            return Ok(done(members));
+        }
+        loop {
+            let mut key = String::new();
+            if keyed {
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) != Some(&b'"') {
+                    return Err(self.error("expected a key"));
+                }
+                key = self.string()?;
+                self.expect(b':')?;
+            }
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.expect(b',')?,
+                Some(b) if *b == close => {
+                    self.expect(close)?;
+                    return Ok(done(members));
+                }
+                _ => return Err(self.error(&format!("expected ',' or '{}'", close as char))),
+            }
+        }
+    }
+
+    /// Copies a string as written and returns it with the escapes undone
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'"') => break,
+                Some(b'\\') => self.pos += 2,
+                Some(b) if *b < 0x20 => return Err(self.error("control character in a string")),
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        self.pos += 1;
+        // Slicing at quotes keeps whole UTF-8 sequences
+        let written = String::from_utf8_lossy(&self.bytes[start..self.pos]).to_string();
+        self.out.push_str(&written);
+        unescape(&written[1..written.len() - 1]).ok_or_else(|| self.error("invalid escape"))
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        if !self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            return Err(self.error("expected a value"));
+        }
+        self.out.push_str(word);
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        let number = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or_default();
+        let value = match number.parse::<f64>() {
+            Ok(value) if is_number(number) => value,
+            _ => {
+                self.pos = start;
+                return Err(self.error("invalid number"));
+            }
+        };
+        self.out.push_str(number);
+        Ok(Value::Number(value))
+    }
+}
+
+/// Whether `text` is a number as JSON writes them: no leading zeros, `+` or
+/// bare `.`, e.g. `-0.5e+3` but not `01`, `1.` or `.5`
+fn is_number(text: &str) -> bool {
+    let digits = |s: &str| s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = text.strip_prefix('-').unwrap_or(text);
+    let int = digits(rest);
+    if int == 0 || (int > 1 && rest.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &rest[int..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let n = digits(fraction);
+        if n == 0 {
+            return false;
+        }
+        rest = &fraction[n..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let n = digits(exponent);
+        if n == 0 {
+            return false;
+        }
+        rest = &exponent[n..];
+    }
+    rest.is_empty()
+}
+
+/// The text of a string's contents with its escapes undone, `None` for an
+/// unknown escape or a lone surrogate
+fn unescape(escaped: &str) -> Option<String> {
+    let mut out = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    let hex = |chars: &mut std::str::Chars| {
+        let digits = chars.by_ref().take(4).collect::<String>();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == 4)
+    };
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = hex(&mut chars)?;
+                if (0xd800..0xdc00).contains(&high) {
+                    if chars.next()? != '\\' || chars.next()? != 'u' {
+                        return None;
+                    }
+                    let low = hex(&mut chars).filter(|low| (0xdc00..0xe000).contains(low))?;
+                    char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))?
+                } else {
+                    char::from_u32(high)?
+                }
+            }
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact, parse, Value, MAX_DEPTH};
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(
+            parse(r#"{"a": [1, -0.5e+3, true, null], "b": "xé😀"}"#),
+            Ok(Value::Object(vec![
+                (
+                    "a".to_string(),
+                    Value::Array(vec![
+                        Value::Number(1.0),
+                        Value::Number(-500.0),
+                        Value::Bool(true),
+                        Value::Null,
+                    ])
+                ),
+                ("b".to_string(), Value::String("xé😀".to_string())),
+            ]))
+        );
+        assert_eq!(
+            compact("{ \"a\" : [ 1 , 2 ] }"),
+            Ok(r#"{"a":[1,2]}"#.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_numbers_json_doesnt_allow() {
+        for number in ["01", "1.", ".5", "+1", "-", "1e", "1e+", "--1", "0x10"] {
+            assert!(parse(number).is_err(), "{number}");
+        }
+        for number in ["0", "-0", "10", "0.5", "1e5", "1E-5", "-1.5e+10"] {
+            assert!(parse(number).is_ok(), "{number}");
+        }
+    }
+
+    #[test]
+    fn caps_the_nesting_depth() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse(&nested(100_000)).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::json;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// Plain lines for reading in a terminal
+    Human,
+    /// One JSON object per line for log pipelines
+    Json,
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+/// Whether `--quiet-success` leaves out everything but warnings and errors
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn init(format: LogFormat, quiet_success: bool) {
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+    QUIET.store(quiet_success, Ordering::Relaxed);
+}
+
+/// Prints `message`, or with `--log-format json` a record carrying the event
+/// name, level, message and `fields`
+fn log(level: &str, event: &str, message: &str, fields: &[(&str, &dyn ToString)]) {
+    if JSON.load(Ordering::Relaxed) {
+        let mut record = vec![
+            ("level", json::string(level)),
+            ("event", json::string(event)),
+            ("message", json::string(message)),
+        ];
+        record.extend(
+            fields
+                .iter()
+                .map(|(key, value)| (*key, json::string(&value.to_string()))),
+        );
+        println!("{}", json::object(&record));
+    } else {
+        println!("{message}");
+    }
+}
+
+pub fn info(event: &str, message: &str, fields: &[(&str, &dyn ToString)]) {
+    if QUIET.load(Ordering::Relaxed) {
+        return;
+    }
+    log("info", event, message, fields);
+}
+
+pub fn warn(event: &str, message: &str, fields: &[(&str, &dyn ToString)]) {
+    log("warn", event, message, fields);
+}
+
+pub fn error(event: &str, message: &str, fields: &[(&str, &dyn ToString)]) {
+    log("error", event, message, fields);
+}
@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether `--always-write` asked for the manifest to be written even when
+/// nothing in it changed
+static ALWAYS_WRITE: AtomicBool = AtomicBool::new(false);
+
+pub fn always_write() {
+    ALWAYS_WRITE.store(true, Ordering::Relaxed);
+}
+
+/// One downloaded image, `file` is relative to the listing directory
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    /// Position of the image on the page, starting at 1
+    pub index: usize,
+    pub file: String,
+    pub url: String,
+    /// The download didn't succeed, `file` is where it should have gone
+    pub failed: bool,
+    /// As the server sent it, empty when unknown
+    pub content_type: String,
+    /// With --dedup-across-output, the identical image elsewhere in the output
+    /// directory that `file` is a hard link to, empty for a copy of its own
+    pub same_as: String,
+    /// With --hash-only, the content hash as 16 hex digits. `file` is empty
+    /// then, the image was never kept
+    pub hash: String,
+    /// With --url-rewrite, the URL the image was actually fetched from,
+    /// empty when it is `url`
+    pub fetched_from: String,
+    /// What --on-conflict did about a file already at the image's path,
+    /// `skipped`, `overwritten` or `renamed`, empty when there was none
+    pub conflict: String,
+    /// With --validate-deep, `ok` or `corrupt` after walking the image's
+    /// structure, empty when it wasn't checked
+    pub integrity: String,
+}
+
+/// Tab separated record of the images saved for a listing, written to
+/// `<output>/<name>/manifest.txt`. The first line is a header naming the
+/// columns so older manifests keep loading when columns are added.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+const COLUMNS: [&str; 10] = [
+    "index",
+    "file",
+    "url",
+    "status",
+    "content_type",
+    "same_as",
+    "hash",
+    "fetched_from",
+    "conflict",
+    "integrity",
+];
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Manifest, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines();
+
+        let header: Vec<&str> = match lines.next() {
+            Some(header) => header.split('\t').collect(),
+            None => return Ok(Manifest::default()),
+        };
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|c| *c == name)
+                .ok_or(format!("Manifest {path} has no {name} column"))
+        };
+        // Manifests from before the index column are already in page order
+        let index_col = header.iter().position(|c| *c == "index");
+        let file_col = column("file")?;
+        let url_col = column("url")?;
+        let status_col = header.iter().position(|c| *c == "status");
+        let content_type_col = header.iter().position(|c| *c == "content_type");
+        let same_as_col = header.iter().position(|c| *c == "same_as");
+        let hash_col = header.iter().position(|c| *c == "hash");
+        let fetched_from_col = header.iter().position(|c| *c == "fetched_from");
+        let conflict_col = header.iter().position(|c| *c == "conflict");
+        let integrity_col = header.iter().position(|c| *c == "integrity");
+
+        let mut entries = Vec::new();
+        for (position, line) in lines.filter(|l| !l.is_empty()).enumerate() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let field = |i: usize| fields.get(i).unwrap_or(&"").to_string();
+            entries.push(ManifestEntry {
+                index: index_col
+                    .and_then(|i| field(i).parse().ok())
+                    .unwrap_or(position + 1),
+                file: field(file_col),
+                url: field(url_col),
+                failed: status_col.is_some_and(|i| field(i) == "failed"),
+                content_type: content_type_col.map(field).unwrap_or_default(),
+                same_as: same_as_col.map(field).unwrap_or_default(),
+                hash: hash_col.map(field).unwrap_or_default(),
+                fetched_from: fetched_from_col.map(field).unwrap_or_default(),
+                conflict: conflict_col.map(field).unwrap_or_default(),
+                integrity: integrity_col.map(field).unwrap_or_default(),
+            });
+        }
+
+        Ok(Manifest { entries })
+    }
+
+    /// Writes the entries in page order. The file is replaced in one rename, so
+    /// it is never seen half written, even when saved mid-run. A manifest that
+    /// already says the same is left alone, keeping its modification time.
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.index);
+
+        let mut text = COLUMNS.join("\t");
+        text.push('\n');
+        for entry in entries {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                entry.index,
+                entry.file,
+                entry.url,
+                if entry.failed { "failed" } else { "ok" },
+                entry.content_type,
+                entry.same_as,
+                entry.hash,
+                entry.fetched_from,
+                entry.conflict,
+                entry.integrity
+            ));
+        }
+        if !ALWAYS_WRITE.load(Ordering::Relaxed)
+            && fs::read_to_string(path).is_ok_and(|old| old == text)
+        {
+            return Ok(());
+        }
+        let part = format!("{path}.part");
+        fs::write(&part, text)?;
+        fs::rename(&part, path)?;
+        Ok(())
+    }
+
+    /// The entry for the image at `url`, see `same_image`
+    pub fn get(&self, url: &str, ignore_query: bool) -> Option<&ManifestEntry> {
+        self.entries
+            .iter()
+            .find(|e| same_image(&e.url, url, ignore_query))
+    }
+}
+
+/// Whether two links are the same image. `ignore_query` leaves the query out,
+/// with --preserve-query the same photo often comes with a new token on each
+/// visit.
+pub fn same_image(a: &str, b: &str, ignore_query: bool) -> bool {
+    if !ignore_query {
+        return a == b;
+    }
+    let path = |link: &str| link.split('?').next().unwrap_or(link).to_string();
+    path(a) == path(b)
+}
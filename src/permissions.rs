@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::fs;
+
+/// Directory mode for a `--mode` given for files: every class that may read
+/// may also list, `0640` becomes `0750`
+#[cfg(unix)]
+fn dir_mode(mode: u32) -> u32 {
+    mode | ((mode & 0o444) >> 2)
+}
+
+/// Sets `mode` on `path` and everything under it, directories get the search
+/// bit wherever `mode` allows reading
+#[cfg(unix)]
+pub fn apply(path: &str, mode: u32) -> Result<(), Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_symlink() {
+        return Ok(());
+    }
+    if metadata.is_dir() {
+        fs::set_permissions(path, fs::Permissions::from_mode(dir_mode(mode)))?;
+        for entry in fs::read_dir(path)?.flatten() {
+            apply(&entry.path().to_string_lossy(), mode)?;
+        }
+    } else {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+/// The user and group ids of a `--chown` `user:group`, each given by name or
+/// number. `None` for a part left out keeps the current owner or group
+#[cfg(unix)]
+fn owner_ids(owner: &str) -> Result<(Option<u32>, Option<u32>), Box<dyn Error>> {
+    use std::ffi::CString;
+
+    let (user, group) = owner.split_once(':').unwrap_or((owner, ""));
+    let uid = match user {
+        "" => None,
+        user => Some(match user.parse() {
+            Ok(uid) => uid,
+            Err(_) => {
+                let name = CString::new(user)?;
+                let entry = unsafe { libc::getpwnam(name.as_ptr()) };
+                if entry.is_null() {
+                    return Err(format!("no user named {user:?}").into());
+                }
+                unsafe { (*entry).pw_uid }
+            }
+        }),
+    };
+    let gid = match group {
+        "" => None,
+        group => Some(match group.parse() {
+            Ok(gid) => gid,
+            Err(_) => {
+                let name = CString::new(group)?;
+                let entry = unsafe { libc::getgrnam(name.as_ptr()) };
+                if entry.is_null() {
+                    return Err(format!("no group named {group:?}").into());
+                }
+                unsafe { (*entry).gr_gid }
+            }
+        }),
+    };
+    Ok((uid, gid))
+}
+
+/// Gives `path` and everything under it to the `user:group` of `owner`.
+/// Symlinks are changed themselves, not what they point to. Needs root or
+/// `CAP_CHOWN` unless only changing to another group the user is in.
+#[cfg(unix)]
+pub fn chown(path: &str, owner: &str) -> Result<(), Box<dyn Error>> {
+    let (uid, gid) = owner_ids(owner)?;
+    chown_ids(path, uid, gid)
+}
+
+#[cfg(unix)]
+fn chown_ids(path: &str, uid: Option<u32>, gid: Option<u32>) -> Result<(), Box<dyn Error>> {
+    std::os::unix::fs::lchown(path, uid, gid).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            format!("{e}, changing the owner needs root or CAP_CHOWN").into()
+        } else {
+            Box::<dyn Error>::from(e)
+        }
+    })?;
+    if fs::symlink_metadata(path)?.is_dir() {
+        for entry in fs::read_dir(path)?.flatten() {
+            chown_ids(&entry.path().to_string_lossy(), uid, gid)?;
+        }
+    }
+    Ok(())
+}
+
+/// Windows has no Unix owners, the files keep the user running the scraper
+#[cfg(not(unix))]
+pub fn chown(_path: &str, _owner: &str) -> Result<(), Box<dyn Error>> {
+    crate::logging::warn(
+        "permissions",
+        "--chown is only supported on Unix, leaving the files to the current user",
+        &[],
+    );
+    Ok(())
+}
+
+/// Windows has no Unix modes, the files keep their default permissions
+#[cfg(not(unix))]
+pub fn apply(_path: &str, _mode: u32) -> Result<(), Box<dyn Error>> {
+    crate::logging::warn(
+        "permissions",
+        "--mode is only supported on Unix, leaving the default permissions",
+        &[],
+    );
+    Ok(())
+}
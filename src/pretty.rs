@@ -0,0 +1,98 @@
+/// Elements without a closing tag
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is copied as is, up to their closing tag
+const RAW_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "pre"];
+
+/// End of the tag starting at `start`, past its `>`, skipping quoted
+/// attribute values
+fn tag_end(html: &str, start: usize) -> usize {
+    if html[start..].starts_with("<!--") {
+        return html[start..]
+            .find("-->")
+            .map_or(html.len(), |end| start + end + 3);
+    }
+    let mut quote = None;
+    for (i, c) in html[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return start + i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Lower case name of `tag`, without the `/` of a closing tag
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// `html` with every tag on a line of its own, indented by how deep it is
+/// nested, for reading. Text is trimmed, the content of scripts, styles and
+/// `pre` is left alone. Not meant to be parsed again, the extraction
+/// patterns expect the page as it was sent.
+pub fn prettify(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() * 2);
+    let mut depth = 0usize;
+    let line = |out: &mut String, depth: usize, text: &str| {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(text);
+        out.push('\n');
+    };
+
+    let mut pos = 0;
+    while pos < html.len() {
+        let Some(start) = html[pos..].find('<').map(|i| pos + i) else {
+            let text = html[pos..].trim();
+            if !text.is_empty() {
+                line(&mut out, depth, text);
+            }
+            break;
+        };
+        let text = html[pos..start].trim();
+        if !text.is_empty() {
+            line(&mut out, depth, text);
+        }
+
+        let end = tag_end(html, start);
+        let tag = &html[start..end];
+        let name = tag_name(tag);
+        if tag.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            line(&mut out, depth, tag);
+        } else if tag.starts_with("<!")
+            || tag.starts_with("<?")
+            || name.is_empty()
+            || VOID_ELEMENTS.contains(&name.as_str())
+            || tag.ends_with("/>")
+        {
+            // Nothing inside, so no deeper
+            line(&mut out, depth, tag);
+        } else if RAW_ELEMENTS.contains(&name.as_str()) {
+            let close = format!("</{name}");
+            let content_end = html[end..]
+                .to_ascii_lowercase()
+                .find(&close)
+                .map_or(html.len(), |i| end + i);
+            let close_end = tag_end(html, content_end.min(html.len()));
+            line(&mut out, depth, &html[start..close_end]);
+            pos = close_end;
+            continue;
+        } else {
+            line(&mut out, depth, tag);
+            depth += 1;
+        }
+        pos = end;
+    }
+    out
+}
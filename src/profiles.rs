@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::fs;
+
+/// Name of the file the profiles are read from, in the working directory or
+/// else the home directory
+pub const FILE_NAME: &str = ".casteelprofiles";
+
+/// The flags of profile `name`. A profile starts with a `[name]` line and
+/// each line after it holds flags as they would be typed, split on
+/// whitespace. Blank lines and lines starting with `#` are skipped.
+fn flags(text: &str, name: &str) -> Option<Vec<String>> {
+    let mut found = None;
+    let mut current = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(section.trim());
+            if current == Some(name) {
+                found.get_or_insert_with(Vec::new);
+            }
+            continue;
+        }
+        if current == Some(name) {
+            if let Some(flags) = found.as_mut() {
+                flags.extend(line.split_whitespace().map(str::to_string));
+            }
+        }
+    }
+    found
+}
+
+fn profiles_file() -> Option<String> {
+    if fs::metadata(FILE_NAME).is_ok() {
+        return Some(FILE_NAME.to_string());
+    }
+    let home = std::env::var("HOME").ok()?;
+    let path = format!("{home}/{FILE_NAME}");
+    fs::metadata(&path).is_ok().then_some(path)
+}
+
+/// The command line with the flags of its `--profile` put in front of the
+/// ones typed, after the subcommand if there is one. Flags given on the
+/// command line override the profile's, since the last value of a flag wins.
+pub fn expand(args: Vec<String>) -> Result<Vec<String>, Box<dyn Error>> {
+    let name = args.iter().enumerate().find_map(|(i, arg)| {
+        arg.strip_prefix("--profile=")
+            .map(str::to_string)
+            .or_else(|| {
+                (arg == "--profile")
+                    .then(|| args.get(i + 1).cloned())
+                    .flatten()
+            })
+    });
+    let Some(name) = name else {
+        return Ok(args);
+    };
+
+    let path = profiles_file().ok_or(format!("No {FILE_NAME} file for --profile {name}"))?;
+    let text = fs::read_to_string(&path).map_err(|e| format!("Unable to read {path}: {e}"))?;
+    let flags = flags(&text, &name).ok_or(format!("No profile {name} in {path}"))?;
+
+    let at = match args.get(1).map(String::as_str) {
+        Some("scrape" | "extract") => 2,
+        _ => 1,
+    };
+    let mut expanded = args[..at].to_vec();
+    expanded.extend(flags);
+    expanded.extend_from_slice(&args[at..]);
+    Ok(expanded)
+}
@@ -0,0 +1,65 @@
+use std::error::Error;
+use std::fs;
+
+/// Proxies from a `--proxy-list` file, handed out round-robin. A proxy that
+/// can't be reached is dropped for the rest of the run.
+#[derive(Debug)]
+pub struct ProxyPool {
+    proxies: Vec<String>,
+    dead: Vec<bool>,
+    next: usize,
+}
+
+impl ProxyPool {
+    /// One proxy URL per line, blank lines and lines starting with `#` are skipped
+    pub fn load(path: &str) -> Result<ProxyPool, Box<dyn Error>> {
+        let proxies = fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        if proxies.is_empty() {
+            return Err(format!("No proxies in {path}").into());
+        }
+        for proxy in &proxies {
+            reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy {proxy}: {e}"))?;
+        }
+
+        Ok(ProxyPool {
+            dead: vec![false; proxies.len()],
+            proxies,
+            next: 0,
+        })
+    }
+
+    /// The next live proxy, `None` once every proxy has failed
+    pub fn next(&mut self) -> Option<String> {
+        for _ in 0..self.proxies.len() {
+            let i = self.next;
+            self.next = (self.next + 1) % self.proxies.len();
+            if !self.dead[i] {
+                return Some(self.proxies[i].clone());
+            }
+        }
+        None
+    }
+
+    pub fn mark_dead(&mut self, proxy: &str) {
+        if let Some(i) = self.proxies.iter().position(|p| p == proxy) {
+            self.dead[i] = true;
+        }
+    }
+}
+
+/// A request failed because its proxy couldn't be reached
+#[derive(Debug)]
+pub struct ProxyError(pub String);
+
+impl std::fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Proxy {} is unreachable", self.0)
+    }
+}
+
+impl Error for ProxyError {}
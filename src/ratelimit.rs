@@ -0,0 +1,472 @@
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{httpdate, logging, shutdown};
+
+/// Requests to make before giving up on a server that keeps answering 429
+const ATTEMPTS: usize = 3;
+
+/// Retries the whole run may still make, `usize::MAX` without `--retry-budget`
+static BUDGET: AtomicUsize = AtomicUsize::new(usize::MAX);
+static USED: AtomicUsize = AtomicUsize::new(0);
+
+/// The statuses `--retry-on` asked to try again, unset without it
+static RETRY_ON: OnceLock<Vec<u16>> = OnceLock::new();
+
+/// Longest wait for a `--retry-on` status without `--wait-for-rate-limit`
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// Whether `--concurrency-limit-from-headers` asked to pace downloads by the
+/// quota the server reports
+static ADAPTIVE: AtomicBool = AtomicBool::new(false);
+/// The requests left and when the window resets, from the last response
+/// that said
+static QUOTA: Mutex<Option<(u64, Instant)>> = Mutex::new(None);
+/// Whether the last pause was longer than the delay, to log only the changes
+static THROTTLING: AtomicBool = AtomicBool::new(false);
+
+/// Requests left at or below which downloads are spread over the rest of
+/// the window
+const LOW_QUOTA: u64 = 10;
+
+/// Longest pause for a reset, in case a server sends a nonsensical one
+const MAX_QUOTA_WAIT: Duration = Duration::from_secs(3600);
+
+/// Reset values above this are Unix times rather than seconds from now
+const EPOCH_RESET: u64 = 1_000_000_000;
+
+/// Consecutive 429 or 5xx answers from one host that open its circuit, 0
+/// without `--circuit-breaker`
+static BREAKER_THRESHOLD: AtomicUsize = AtomicUsize::new(0);
+/// How long an open circuit holds requests back before letting one through
+static BREAKER_COOLDOWN: Mutex<Duration> = Mutex::new(Duration::from_secs(60));
+static CIRCUITS: Mutex<Vec<(String, Circuit)>> = Mutex::new(Vec::new());
+
+/// Where a host's circuit is at
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Circuit {
+    /// Requests go through, counting the failures in a row
+    Closed(usize),
+    /// No requests until then
+    Open(Instant),
+    /// One request goes through to see whether the host recovered
+    HalfOpen,
+}
+
+/// Caps the retries of every request in the run together at `budget`
+pub fn set_budget(budget: Option<usize>) {
+    BUDGET.store(budget.unwrap_or(usize::MAX), Ordering::SeqCst);
+}
+
+/// Tries the responses with these statuses again, see `wait`
+pub fn set_retry_on(statuses: Vec<u16>) {
+    RETRY_ON.set(statuses).ok();
+}
+
+/// Whether a response with `status` may be worth trying again: the
+/// `--retry-on` statuses when given, otherwise only 429 Too Many Requests.
+/// Only answers are classified; a request that got none, e.g. a refused
+/// connection or a timeout, is never retried, only moved to the next proxy
+/// of a `--proxy-list`.
+pub fn is_retryable(status: u16) -> bool {
+    retryable(status, RETRY_ON.get().map(Vec::as_slice))
+}
+
+fn retryable(status: u16, retry_on: Option<&[u16]>) -> bool {
+    match retry_on {
+        Some(statuses) => statuses.contains(&status),
+        None => status == 429,
+    }
+}
+
+/// How long `wait` waits before an attempt, `None` when it doesn't retry:
+/// the `asked` Retry-After capped at `max_wait`, or at `MAX_RETRY_WAIT` with
+/// `--retry-on`, or with `--retry-on` and nothing asked the backoff
+fn retry_wait(
+    asked: Option<Duration>,
+    max_wait: Option<Duration>,
+    retry_on: bool,
+    attempt: usize,
+) -> Option<Duration> {
+    match (asked, max_wait, retry_on) {
+        (Some(asked), Some(max_wait), _) => Some(asked.min(max_wait)),
+        (Some(asked), None, true) => Some(asked.min(MAX_RETRY_WAIT)),
+        (None, _, true) => Some(Duration::from_secs(1 << attempt.min(6))),
+        _ => None,
+    }
+}
+
+/// Paces downloads by the rate limit headers of the responses, see `pace`
+pub fn enable_adaptive() {
+    ADAPTIVE.store(true, Ordering::SeqCst);
+}
+
+/// The curl `-w` variables for the headers `observe` reads, one per line
+pub const CURL_HEADERS: &str = "%header{x-ratelimit-remaining}\n%header{x-ratelimit-reset}\n%header{ratelimit-remaining}\n%header{ratelimit-reset}\n%header{ratelimit}";
+
+/// Seconds until the window resets from a reset header, either seconds from
+/// now or, from servers like GitHub's, a Unix time. `None` for anything
+/// else, e.g. `inf` or a time too far out for a `Duration`
+fn reset_in(reset: &str) -> Option<Duration> {
+    let reset = reset
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|r| r.is_finite() && *r >= 0.0)?;
+    if reset < EPOCH_RESET as f64 {
+        return Duration::try_from_secs_f64(reset).ok();
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs_f64();
+    Duration::try_from_secs_f64((reset - now).max(0.0)).ok()
+}
+
+/// Keeps the quota a response reported, from the values of
+/// `CURL_HEADERS` in that order: `X-RateLimit-Remaining`/`-Reset`,
+/// `RateLimit-Remaining`/`-Reset` or the combined
+/// `RateLimit: limit=100, remaining=10, reset=30`. Responses without them
+/// leave the last quota as it is.
+pub fn observe(values: &[&str]) {
+    if !ADAPTIVE.load(Ordering::SeqCst) {
+        return;
+    }
+    let value = |i: usize| values.get(i).map(|v| v.trim()).filter(|v| !v.is_empty());
+    let combined = value(4).map(|header| {
+        let param = |name: &str| {
+            header.split(',').find_map(|part| {
+                let (key, value) = part.split_once('=')?;
+                (key.trim().eq_ignore_ascii_case(name)).then(|| value.trim().to_string())
+            })
+        };
+        (param("remaining"), param("reset"))
+    });
+    let (remaining, reset) = match (value(0), value(2), combined) {
+        (Some(remaining), _, _) => (Some(remaining.to_string()), value(1).map(str::to_string)),
+        (None, Some(remaining), _) => (Some(remaining.to_string()), value(3).map(str::to_string)),
+        (None, None, Some(combined)) => combined,
+        _ => return,
+    };
+    let Some(remaining) = remaining.and_then(|r| r.parse::<u64>().ok()) else {
+        return;
+    };
+    // Without a reset, assume the window is a minute as most are
+    let reset = reset
+        .as_deref()
+        .and_then(reset_in)
+        .unwrap_or(Duration::from_secs(60));
+    *QUOTA.lock().unwrap() = Some((remaining, Instant::now() + reset));
+}
+
+/// How long to wait before the next download instead of `delay`. With a
+/// quota from `observe` it pauses until the reset once nothing is left and
+/// spreads the last `LOW_QUOTA` requests over the rest of the window, never
+/// going faster than `delay`. Just `delay` without one, or once the window
+/// has reset.
+pub fn pace(delay: Duration) -> Duration {
+    if !ADAPTIVE.load(Ordering::SeqCst) {
+        return delay;
+    }
+    let quota = *QUOTA.lock().unwrap();
+    let Some((remaining, reset)) = quota else {
+        return delay;
+    };
+    let left = reset.saturating_duration_since(Instant::now());
+    let wanted = match remaining {
+        _ if left.is_zero() => Duration::ZERO,
+        0 => left,
+        r if r <= LOW_QUOTA => left / (r as u32 + 1),
+        _ => Duration::ZERO,
+    }
+    .min(MAX_QUOTA_WAIT);
+    if wanted <= delay {
+        if THROTTLING.swap(false, Ordering::SeqCst) {
+            logging::info(
+                "throttle",
+                "The server's quota is fine again, back to the --delay",
+                &[("remaining", &remaining)],
+            );
+        }
+        return delay;
+    }
+    let message = if remaining == 0 {
+        format!(
+            "The server's quota is used up, pausing {}s until it resets",
+            wanted.as_secs()
+        )
+    } else {
+        format!(
+            "Only {remaining} request(s) left for {}s, slowing down to one every {}s",
+            left.as_secs(),
+            wanted.as_secs()
+        )
+    };
+    // Slowing down is logged once, every pause for a reset
+    let started = !THROTTLING.swap(true, Ordering::SeqCst);
+    if started || remaining == 0 {
+        logging::warn(
+            "throttle",
+            &message,
+            &[
+                ("remaining", &remaining),
+                ("reset", &left.as_secs()),
+                ("seconds", &wanted.as_secs()),
+            ],
+        );
+    }
+    wanted
+}
+
+/// Holds back every request to a host once `threshold` of them in a row
+/// were answered 429 or 5xx, for `cooldown`, see `admit`
+pub fn set_breaker(threshold: usize, cooldown: Duration) {
+    BREAKER_THRESHOLD.store(threshold, Ordering::SeqCst);
+    *BREAKER_COOLDOWN.lock().unwrap() = cooldown;
+}
+
+fn circuit(host: &str) -> Circuit {
+    CIRCUITS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(h, _)| h == host)
+        .map_or(Circuit::Closed(0), |(_, c)| *c)
+}
+
+fn set_circuit(host: &str, state: Circuit) {
+    let mut circuits = CIRCUITS.lock().unwrap();
+    match circuits.iter_mut().find(|(h, _)| h == host) {
+        Some((_, c)) => *c = state,
+        None => circuits.push((host.to_string(), state)),
+    }
+}
+
+/// Waits before a request to `host` while its circuit is open, until the
+/// cooldown is over and the circuit half-open lets this request through as
+/// the probe. Returns right away when the circuit is closed or the run is
+/// asked to stop.
+pub fn admit(host: &str) {
+    if BREAKER_THRESHOLD.load(Ordering::SeqCst) == 0 {
+        return;
+    }
+    let Circuit::Open(until) = circuit(host) else {
+        return;
+    };
+    let left = until.saturating_duration_since(Instant::now());
+    if !left.is_zero() {
+        logging::warn(
+            "circuit",
+            &format!("Holding requests to {host} for {:.1}s", left.as_secs_f64()),
+            &[
+                ("host", &host),
+                ("state", &"open"),
+                ("seconds", &left.as_secs()),
+            ],
+        );
+        shutdown::sleep(left);
+        if shutdown::requested().is_some() {
+            return;
+        }
+    }
+    logging::info(
+        "circuit",
+        &format!("Trying {host} again with one request"),
+        &[("host", &host), ("state", &"half-open")],
+    );
+    set_circuit(host, Circuit::HalfOpen);
+}
+
+/// Counts the answer with `status` from `host` towards opening its
+/// circuit, or closes it again when the probe of a half-open one succeeded
+pub fn record_status(host: &str, status: u16) {
+    let threshold = BREAKER_THRESHOLD.load(Ordering::SeqCst);
+    if threshold == 0 {
+        return;
+    }
+    let failed = status == 429 || (500..600).contains(&status);
+    let cooldown = *BREAKER_COOLDOWN.lock().unwrap();
+    let open = |reason: String| {
+        logging::warn(
+            "circuit",
+            &format!(
+                "{reason}, pausing requests to {host} for {}s",
+                cooldown.as_secs()
+            ),
+            &[
+                ("host", &host),
+                ("state", &"open"),
+                ("status", &status),
+                ("seconds", &cooldown.as_secs()),
+            ],
+        );
+        set_circuit(host, Circuit::Open(Instant::now() + cooldown));
+    };
+    match (circuit(host), failed) {
+        (Circuit::Closed(failures), true) if failures + 1 >= threshold => open(format!(
+            "{host} answered {status}, {} failure(s) in a row",
+            failures + 1
+        )),
+        (Circuit::Closed(failures), true) => set_circuit(host, Circuit::Closed(failures + 1)),
+        (Circuit::HalfOpen, true) => open(format!("{host} still answers {status}")),
+        (Circuit::HalfOpen, false) => {
+            logging::info(
+                "circuit",
+                &format!("{host} answered {status}, resuming requests to it"),
+                &[("host", &host), ("state", &"closed"), ("status", &status)],
+            );
+            set_circuit(host, Circuit::Closed(0));
+        }
+        (_, false) => set_circuit(host, Circuit::Closed(0)),
+        // A request that was already under way when the circuit opened
+        (Circuit::Open(_), true) => {}
+    }
+}
+
+/// Retries made so far in the run
+pub fn retries_used() -> usize {
+    USED.load(Ordering::SeqCst)
+}
+
+/// The server answered 429 Too Many Requests, or another status
+/// `is_retryable` accepts
+#[derive(Debug)]
+pub struct RateLimited {
+    pub status: u16,
+    /// The raw `Retry-After` header, if the server sent one
+    pub retry_after: Option<String>,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.status, &self.retry_after) {
+            (429, Some(retry_after)) => write!(
+                f,
+                "Rate limited, the server sent Retry-After: {retry_after}"
+            ),
+            (429, None) => write!(f, "Rate limited by the server"),
+            (status, Some(retry_after)) => write!(
+                f,
+                "The server answered {status} with Retry-After: {retry_after}"
+            ),
+            (status, None) => write!(f, "The server answered {status}"),
+        }
+    }
+}
+
+impl Error for RateLimited {}
+
+/// Waits before trying `url` again after a response with `status`, returning
+/// whether to. Without `--retry-on` only a 429 is retried, and only with
+/// `--wait-for-rate-limit` and a `Retry-After` header, waiting as long as it
+/// asks but at most `max_wait`. The `--retry-on` statuses are retried either
+/// way, backing off 2s, 4s, ... when the server doesn't say how long. Never
+/// after the last attempt or once the run's retry budget is spent.
+pub fn wait(
+    max_wait: Option<Duration>,
+    url: &str,
+    status: u16,
+    retry_after: Option<&str>,
+    attempt: usize,
+) -> bool {
+    if !is_retryable(status) {
+        return false;
+    }
+    let asked = retry_after.and_then(httpdate::retry_after);
+    let Some(wait) = retry_wait(asked, max_wait, RETRY_ON.get().is_some(), attempt) else {
+        return false;
+    };
+    if attempt >= ATTEMPTS || shutdown::requested().is_some() {
+        return false;
+    }
+    if retries_used() >= BUDGET.load(Ordering::SeqCst) {
+        logging::warn(
+            "retry_budget",
+            "The --retry-budget is used up, not waiting for the rate limit",
+            &[("url", &url), ("used", &retries_used())],
+        );
+        return false;
+    }
+    USED.fetch_add(1, Ordering::SeqCst);
+
+    let message = match (status, asked) {
+        (429, Some(_)) => format!(
+            "Rate limited, waiting {}s as the server asked",
+            wait.as_secs()
+        ),
+        (429, None) => format!("Rate limited, trying again in {}s", wait.as_secs()),
+        (status, Some(_)) => format!(
+            "The server answered {status}, waiting {}s as it asked",
+            wait.as_secs()
+        ),
+        (status, None) => format!(
+            "The server answered {status}, trying again in {}s",
+            wait.as_secs()
+        ),
+    };
+    logging::warn(
+        "rate_limit",
+        &message,
+        &[
+            ("url", &url),
+            ("status", &status),
+            ("seconds", &wait.as_secs()),
+            ("attempt", &attempt),
+        ],
+    );
+    shutdown::sleep(wait);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retry_wait, retryable, MAX_RETRY_WAIT};
+    use std::time::Duration;
+
+    #[test]
+    fn only_429_is_retryable_by_default() {
+        assert!(retryable(429, None));
+        for status in [500, 502, 503, 404] {
+            assert!(!retryable(status, None), "{status}");
+        }
+    }
+
+    #[test]
+    fn retry_on_replaces_the_default() {
+        let retry_on = [502, 503];
+        assert!(retryable(502, Some(&retry_on)));
+        assert!(retryable(503, Some(&retry_on)));
+        // Outside the list, 429 included, since the list replaces the default
+        assert!(!retryable(500, Some(&retry_on)));
+        assert!(!retryable(429, Some(&retry_on)));
+    }
+
+    #[test]
+    fn retry_wait_follows_the_decision_table() {
+        let secs = Duration::from_secs;
+        // Retry-After with --wait-for-rate-limit, capped at its maximum
+        assert_eq!(
+            retry_wait(Some(secs(5)), Some(secs(30)), false, 1),
+            Some(secs(5))
+        );
+        assert_eq!(
+            retry_wait(Some(secs(90)), Some(secs(30)), true, 1),
+            Some(secs(30))
+        );
+        // Retry-After with only --retry-on, capped at MAX_RETRY_WAIT
+        assert_eq!(
+            retry_wait(Some(secs(600)), None, true, 1),
+            Some(MAX_RETRY_WAIT)
+        );
+        // Nothing asked with --retry-on backs off
+        assert_eq!(retry_wait(None, None, true, 1), Some(secs(2)));
+        assert_eq!(retry_wait(None, Some(secs(30)), true, 2), Some(secs(4)));
+        assert_eq!(retry_wait(None, None, true, 60), Some(secs(64)));
+        // Without --retry-on a 429 is only retried as asked and allowed
+        assert_eq!(retry_wait(Some(secs(5)), None, false, 1), None);
+        assert_eq!(retry_wait(None, Some(secs(30)), false, 1), None);
+        assert_eq!(retry_wait(None, None, false, 1), None);
+    }
+}
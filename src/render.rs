@@ -0,0 +1,61 @@
+use std::error::Error;
+
+/// Loads `url` in a headless Chromium and returns the DOM after scripts ran,
+/// which has the photos Compass only adds from JavaScript.
+/// Needs a Chromium or Chrome binary, `browser` is its name or path.
+pub fn get_rendered_html(
+    browser: &str,
+    url: &str,
+    user_agent: &str,
+) -> Result<String, Box<dyn Error>> {
+    let output = std::process::Command::new(browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg(format!("--user-agent={user_agent}"))
+        .arg("--dump-dom")
+        .arg(url)
+        .output()
+        .map_err(|e| format!("Unable to run {browser}: {e}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{browser} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Saves a PNG of `url` as the headless browser shows it to `path`. Chromium
+/// only captures its window, so the window is made tall enough for most
+/// listing pages.
+pub fn save_screenshot(
+    browser: &str,
+    url: &str,
+    user_agent: &str,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let output = std::process::Command::new(browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--hide-scrollbars")
+        .arg("--window-size=1280,8000")
+        .arg(format!("--user-agent={user_agent}"))
+        .arg(format!("--screenshot={path}"))
+        .arg(url)
+        .output()
+        .map_err(|e| format!("Unable to run {browser}: {e}"))?;
+
+    if !output.status.success() || !std::path::Path::new(path).exists() {
+        return Err(format!(
+            "{browser} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,389 @@
+use regex::Regex;
+
+use crate::json::Value;
+
+/// Keywords that only describe the schema and have nothing to check
+const ANNOTATIONS: [&str; 8] = [
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "format",
+];
+
+/// Keywords `check` knows, everything else is reported by `unsupported`
+const KEYWORDS: [&str; 19] = [
+    "type",
+    "enum",
+    "const",
+    "required",
+    "properties",
+    "additionalProperties",
+    "items",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "anyOf",
+    "allOf",
+];
+
+/// One constraint a value didn't meet
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// JSON Pointer to the value, "" for the whole document
+    pub path: String,
+    /// JSON Pointer to the constraint in the schema
+    pub constraint: String,
+    pub message: String,
+}
+
+/// Appends an object key or array index to a JSON Pointer
+fn pointer(path: &str, token: &str) -> String {
+    format!("{path}/{}", token.replace('~', "~0").replace('/', "~1"))
+}
+
+/// A short form of `value` for messages
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{s:?}"),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "null".to_string(),
+        _ => format!("an {}", value.type_name()),
+    }
+}
+
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value {
+        Some(Value::Number(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Whether `value` is of JSON Schema type `name`, integers being numbers too
+fn is_type(value: &Value, name: &str) -> bool {
+    value.type_name() == name || (name == "number" && value.type_name() == "integer")
+}
+
+/// Keywords in `schema` that `check` doesn't look at, as JSON Pointers into
+/// it, so a constraint isn't quietly taken as met
+pub fn unsupported(schema: &Value) -> Vec<String> {
+    let mut found = Vec::new();
+    unsupported_at(schema, "", &mut found);
+    found
+}
+
+fn unsupported_at(schema: &Value, at: &str, found: &mut Vec<String>) {
+    let Value::Object(members) = schema else {
+        return;
+    };
+    for (keyword, value) in members {
+        let here = pointer(at, keyword);
+        match (keyword.as_str(), value) {
+            ("properties", Value::Object(properties)) => {
+                for (name, sub) in properties {
+                    unsupported_at(sub, &pointer(&here, name), found);
+                }
+            }
+            ("anyOf" | "allOf", Value::Array(subs)) => {
+                for (i, sub) in subs.iter().enumerate() {
+                    unsupported_at(sub, &pointer(&here, &i.to_string()), found);
+                }
+            }
+            ("items" | "additionalProperties", sub) => unsupported_at(sub, &here, found),
+            (keyword, _) if KEYWORDS.contains(&keyword) || ANNOTATIONS.contains(&keyword) => {}
+            _ => found.push(here),
+        }
+    }
+}
+
+/// Every constraint of `schema` that `value` doesn't meet. Covers the
+/// keywords in `KEYWORDS` of JSON Schema 2020-12, which is enough for the
+/// flat objects --extra-json and --enrich-url bring in.
+pub fn check(schema: &Value, value: &Value) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_at(schema, "", value, "", &mut violations);
+    violations
+}
+
+fn check_at(schema: &Value, at: &str, value: &Value, path: &str, out: &mut Vec<Violation>) {
+    match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            out.push(Violation {
+                path: path.to_string(),
+                constraint: at.to_string(),
+                message: "no value is allowed here".to_string(),
+            });
+            return;
+        }
+        Value::Object(_) => {}
+        _ => return,
+    }
+    let mut fail = |keyword: &str, message: String| {
+        out.push(Violation {
+            path: path.to_string(),
+            constraint: pointer(at, keyword),
+            message,
+        })
+    };
+
+    match schema.get("type") {
+        Some(Value::String(name)) if !is_type(value, name) => {
+            fail("type", format!("{} is not of type {name}", show(value)))
+        }
+        Some(Value::Array(names))
+            if !names
+                .iter()
+                .any(|n| matches!(n, Value::String(name) if is_type(value, name))) =>
+        {
+            let names = names.iter().map(show).collect::<Vec<String>>();
+            fail(
+                "type",
+                format!("{} is not of type {}", show(value), names.join(" or ")),
+            )
+        }
+        _ => {}
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed = allowed.iter().map(show).collect::<Vec<String>>();
+            fail(
+                "enum",
+                format!("{} is not one of {}", show(value), allowed.join(", ")),
+            );
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            fail(
+                "const",
+                format!("{} is not {}", show(value), show(expected)),
+            );
+        }
+    }
+
+    match value {
+        Value::Object(members) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required {
+                    if let Value::String(name) = name {
+                        if value.get(name).is_none() {
+                            fail("required", format!("{name:?} is missing"));
+                        }
+                    }
+                }
+            }
+            let properties = schema.get("properties");
+            for (name, member) in members {
+                let member_path = pointer(path, name);
+                match properties.and_then(|p| p.get(name)) {
+                    Some(sub) => check_at(
+                        sub,
+                        &pointer(&pointer(at, "properties"), name),
+                        member,
+                        &member_path,
+                        out,
+                    ),
+                    None => {
+                        if let Some(sub) = schema.get("additionalProperties") {
+                            if *sub == Value::Bool(false) {
+                                out.push(Violation {
+                                    path: member_path,
+                                    constraint: pointer(at, "additionalProperties"),
+                                    message: format!("{name:?} is not an allowed property"),
+                                });
+                            } else {
+                                check_at(
+                                    sub,
+                                    &pointer(at, "additionalProperties"),
+                                    member,
+                                    &member_path,
+                                    out,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as f64;
+            if let Some(min) = number(schema.get("minItems")).filter(|min| len < *min) {
+                fail("minItems", format!("{len} item(s), fewer than {min}"));
+            }
+            if let Some(max) = number(schema.get("maxItems")).filter(|max| len > *max) {
+                fail("maxItems", format!("{len} item(s), more than {max}"));
+            }
+            if schema.get("uniqueItems") == Some(&Value::Bool(true))
+                && items
+                    .iter()
+                    .enumerate()
+                    .any(|(i, item)| items[..i].contains(item))
+            {
+                fail("uniqueItems", "the items are not unique".to_string());
+            }
+            if let Some(sub) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check_at(
+                        sub,
+                        &pointer(at, "items"),
+                        item,
+                        &pointer(path, &i.to_string()),
+                        out,
+                    );
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = *n;
+            if let Some(min) = number(schema.get("minimum")).filter(|min| n < *min) {
+                fail("minimum", format!("{n} is less than the minimum {min}"));
+            }
+            if let Some(max) = number(schema.get("maximum")).filter(|max| n > *max) {
+                fail("maximum", format!("{n} is more than the maximum {max}"));
+            }
+            if let Some(min) = number(schema.get("exclusiveMinimum")).filter(|min| n <= *min) {
+                fail("exclusiveMinimum", format!("{n} is not more than {min}"));
+            }
+            if let Some(max) = number(schema.get("exclusiveMaximum")).filter(|max| n >= *max) {
+                fail("exclusiveMaximum", format!("{n} is not less than {max}"));
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as f64;
+            if let Some(min) = number(schema.get("minLength")).filter(|min| len < *min) {
+                fail(
+                    "minLength",
+                    format!("{} is shorter than {min} character(s)", show(value)),
+                );
+            }
+            if let Some(max) = number(schema.get("maxLength")).filter(|max| len > *max) {
+                fail(
+                    "maxLength",
+                    format!("{} is longer than {max} character(s)", show(value)),
+                );
+            }
+            if let Some(Value::String(pattern)) = schema.get("pattern") {
+                match Regex::new(pattern) {
+                    Ok(re) if re.is_match(s) => {}
+                    Ok(_) => fail(
+                        "pattern",
+                        format!("{} doesn't match {pattern:?}", show(value)),
+                    ),
+                    Err(e) => fail(
+                        "pattern",
+                        format!("{pattern:?} is not a valid pattern: {e}"),
+                    ),
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Array(subs)) = schema.get("allOf") {
+        for (i, sub) in subs.iter().enumerate() {
+            check_at(
+                sub,
+                &pointer(&pointer(at, "allOf"), &i.to_string()),
+                value,
+                path,
+                out,
+            );
+        }
+    }
+    if let Some(Value::Array(subs)) = schema.get("anyOf") {
+        let met = subs.iter().any(|sub| {
+            let mut violations = Vec::new();
+            check_at(sub, at, value, path, &mut violations);
+            violations.is_empty()
+        });
+        if !met {
+            out.push(Violation {
+                path: path.to_string(),
+                constraint: pointer(at, "anyOf"),
+                message: format!("{} matches none of the {} schemas", show(value), subs.len()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, pointer};
+    use crate::json::parse;
+
+    /// The paths and constraints of the violations of `value` against `schema`
+    fn violations(schema: &str, value: &str) -> Vec<(String, String)> {
+        check(&parse(schema).unwrap(), &parse(value).unwrap())
+            .into_iter()
+            .map(|v| (v.path, v.constraint))
+            .collect()
+    }
+
+    fn pair(path: &str, constraint: &str) -> (String, String) {
+        (path.to_string(), constraint.to_string())
+    }
+
+    #[test]
+    fn integers_are_numbers_but_not_the_other_way_round() {
+        assert!(violations(r#"{"type": "number"}"#, "3").is_empty());
+        assert!(violations(r#"{"type": "integer"}"#, "3.0").is_empty());
+        assert_eq!(
+            violations(r#"{"type": "integer"}"#, "3.5"),
+            [pair("", "/type")]
+        );
+        assert!(violations(r#"{"type": ["string", "number"]}"#, "3.5").is_empty());
+    }
+
+    #[test]
+    fn reports_missing_and_unexpected_properties() {
+        let schema = r#"{
+            "required": ["rating"],
+            "properties": {"rating": {"maximum": 5}},
+            "additionalProperties": false
+        }"#;
+        assert!(violations(schema, r#"{"rating": 4}"#).is_empty());
+        assert_eq!(
+            violations(schema, r#"{"notes": "x"}"#),
+            [
+                pair("", "/required"),
+                pair("/notes", "/additionalProperties")
+            ]
+        );
+        assert_eq!(
+            violations(schema, r#"{"rating": 7}"#),
+            [pair("/rating", "/properties/rating/maximum")]
+        );
+    }
+
+    #[test]
+    fn any_of_needs_one_schema_to_match() {
+        let schema = r#"{"anyOf": [{"type": "string"}, {"maximum": 3}]}"#;
+        assert!(violations(schema, r#""x""#).is_empty());
+        assert!(violations(schema, "2").is_empty());
+        assert_eq!(violations(schema, "4"), [pair("", "/anyOf")]);
+    }
+
+    #[test]
+    fn escapes_pointer_tokens() {
+        assert_eq!(pointer("", "a/b~c"), "/a~1b~0c");
+        assert_eq!(
+            violations(
+                r#"{"properties": {"a/b": {"type": "string"}}}"#,
+                r#"{"a/b": 1}"#
+            ),
+            [pair("/a~1b", "/properties/a~1b/type")]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::logging;
+
+/// The signal that asked the run to stop, 0 while none has arrived
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Stands in for a signal when `--max-runtime` ran out
+pub const TIMEOUT: i32 = -1;
+
+/// Exit status after `--max-runtime`, the same as timeout(1) uses
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// How long a run past `--max-runtime` gets to finish the current download
+/// before it is ended without writing the manifest
+const TIMEOUT_GRACE: Duration = Duration::from_secs(60);
+
+#[cfg(unix)]
+extern "C" fn handle(signal: libc::c_int) {
+    // A second signal means the user doesn't want to wait for the current image
+    if SIGNAL.swap(signal, Ordering::SeqCst) != 0 {
+        unsafe { libc::_exit(exit_code(signal)) };
+    }
+}
+
+/// Catches SIGTERM and Ctrl-C so the downloads stop between two images and the
+/// manifest is still written. Does nothing off Unix.
+pub fn install() {
+    #[cfg(unix)]
+    unsafe {
+        let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGINT, handler);
+    }
+}
+
+/// The signal received, if the run should stop
+pub fn requested() -> Option<i32> {
+    match SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Exit status of a run stopped by `signal`, 128 + the signal like a shell reports
+pub fn exit_code(signal: i32) -> i32 {
+    if signal == TIMEOUT {
+        return TIMEOUT_EXIT_CODE;
+    }
+    128 + signal
+}
+
+/// Asks the run to stop once `limit` has passed, as if a signal had arrived.
+/// A download that hangs past the grace period ends the process outright.
+pub fn stop_after(limit: Duration) {
+    std::thread::spawn(move || {
+        std::thread::sleep(limit);
+        if SIGNAL
+            .compare_exchange(0, TIMEOUT, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return;
+        }
+        logging::warn(
+            "timeout",
+            &format!(
+                "Reached the --max-runtime of {}s, stopping after the current download",
+                limit.as_secs()
+            ),
+            &[("seconds", &limit.as_secs())],
+        );
+        std::thread::sleep(TIMEOUT_GRACE);
+        logging::error(
+            "timeout",
+            "Still running after --max-runtime and its grace period, exiting",
+            &[("grace", &TIMEOUT_GRACE.as_secs())],
+        );
+        std::process::exit(TIMEOUT_EXIT_CODE);
+    });
+}
+
+/// Sleeps for `duration`, returning early once a stop is requested
+pub fn sleep(duration: Duration) {
+    let end = Instant::now() + duration;
+    while requested().is_none() {
+        let now = Instant::now();
+        if now >= end {
+            break;
+        }
+        std::thread::sleep((end - now).min(Duration::from_millis(100)));
+    }
+}
+
+/// Exits with the stop signal's status if one arrived, called once the
+/// manifest has been written
+pub fn exit_if_requested() {
+    if let Some(signal) = requested() {
+        if signal == TIMEOUT {
+            logging::warn(
+                "stopped",
+                "Stopped at --max-runtime, the images so far are in the manifest",
+                &[("signal", &"timeout")],
+            );
+        } else {
+            logging::warn(
+                "stopped",
+                &format!("Stopped by signal {signal}, the images so far are in the manifest"),
+                &[("signal", &signal)],
+            );
+        }
+        std::process::exit(exit_code(signal));
+    }
+}
@@ -0,0 +1,129 @@
+use regex::Regex;
+
+/// How to pull images and the description out of one site's pages.
+/// Supporting a new site is one more entry in `SITES`.
+pub struct Site {
+    pub name: &'static str,
+    /// The listing URL (or saved file name) contains one of these
+    pub hosts: &'static [&'static str],
+    /// Image link patterns, their matches are merged in page order
+    pub link_patterns: Vec<Regex>,
+    /// Description pattern and the capture group holding the text
+    pub info_pattern: Option<(Regex, usize)>,
+    /// How many times the page repeats each image link
+    pub matches_per_image: usize,
+    /// Seconds to wait between two downloads, picked at random in this range,
+    /// unless `--delay` is given
+    pub delay: (u64, u64),
+    /// What a listing ID looks like and the listing URL it goes in, as
+    /// `{id}`, for `--listing-id`
+    pub listing_url: Option<(Regex, &'static str)>,
+    /// Links to other listings on the site, nearby and comparable homes, with
+    /// the path in the first group and the origin to put in front of it
+    pub comparable_links: Option<(Regex, &'static str)>,
+}
+
+lazy_static::lazy_static! {
+    pub static ref SITES: Vec<Site> = vec![
+        Site {
+            name: "compass",
+            hosts: &["compass"],
+            link_patterns: vec![
+                Regex::new(r"[a-zA-Z/\d_\.:]*origin\.webp").unwrap(),
+                // Floor plans and agent uploads keep their original format
+                Regex::new(r"[a-zA-Z/\d_\.:]*origin\.(?:jpe?g|png)").unwrap(),
+                Regex::new(r"[a-zA-Z/\d_\.:]*origin\.avif").unwrap(),
+            ],
+            info_pattern: Some((
+                Regex::new(r"(</span>\.\.\.<span class=.[\s\w-]*.>)(.*)(</span></div><button)").unwrap(),
+                2,
+            )),
+            matches_per_image: 1,
+            delay: (2, 7),
+            listing_url: Some((
+                Regex::new(r"^\d+$").unwrap(),
+                "https://www.compass.com/listing/{id}/",
+            )),
+            comparable_links: Some((
+                Regex::new(r"(?:https://www\.compass\.com)?(/listing/[\w-]+/\d+/)").unwrap(),
+                "https://www.compass.com",
+            )),
+        },
+        Site {
+            name: "zillow",
+            hosts: &["zillow"],
+            link_patterns: vec![
+                Regex::new(r"https://photos.zillowstatic.com/fp/[\w\d]*-uncropped_scaled_within_1536_1152\.jpg").unwrap(),
+                Regex::new(r"https://photos.zillowstatic.com/fp/[\w\d]*-uncropped_scaled_within_1536_1152\.png").unwrap(),
+            ],
+            info_pattern: Some((
+                Regex::new(r"(\\.description\\.:\\.)(.*)(\\.,\\.whatILove\\.)").unwrap(),
+                2,
+            )),
+            matches_per_image: 2,
+            // Zillow starts answering with captchas sooner than most
+            delay: (4, 10),
+            // The zpid, the number before `_zpid` in a listing URL
+            listing_url: Some((
+                Regex::new(r"^\d+$").unwrap(),
+                "https://www.zillow.com/homedetails/{id}_zpid/",
+            )),
+            comparable_links: Some((
+                Regex::new(r"(?:https://www\.zillow\.com)?(/homedetails/[\w-]+/\d+_zpid/)").unwrap(),
+                "https://www.zillow.com",
+            )),
+        },
+    ];
+
+    /// Any absolute webp, jpeg or avif link, for sites without an entry
+    pub static ref GENERIC: Site = Site {
+        name: "generic",
+        hosts: &[],
+        link_patterns: vec![
+            Regex::new(r#"https?://[^"'\s<>()\\]+\.(?:webp|jpe?g|avif)"#).unwrap(),
+        ],
+        info_pattern: None,
+        matches_per_image: 1,
+        delay: (2, 7),
+        listing_url: None,
+        comparable_links: None,
+    };
+
+    /// Linked PDFs, floor plans, disclosures and brochures, for --download-docs
+    pub static ref DOCUMENT_LINK: Regex = Regex::new(r#"https?://[^"'\s<>()\\]+\.pdf"#).unwrap();
+
+    /// Directly hosted video files, for --download-media
+    pub static ref VIDEO_FILE_LINK: Regex = Regex::new(r#"https?://[^"'\s<>()\\]+\.mp4"#).unwrap();
+
+    /// Video players embedded from YouTube or Vimeo, recorded but never downloaded
+    pub static ref VIDEO_EMBED_LINK: Regex = Regex::new(
+        r#"https?://(?:www\.youtube\.com/(?:embed/|watch\?v=)|youtu\.be/|player\.vimeo\.com/video/)[\w-]+"#
+    ).unwrap();
+
+    /// Matterport 3D tours, recorded but never downloaded
+    pub static ref VIRTUAL_TOUR_LINK: Regex = Regex::new(r#"https?://my\.matterport\.com/show/\?m=\w+"#).unwrap();
+}
+
+/// The listing URL for `id` on the site called `name`
+pub fn listing_url(name: &str, id: &str) -> Result<String, String> {
+    let site = SITES
+        .iter()
+        .find(|site| site.name == name)
+        .ok_or(format!("unknown site {name}"))?;
+    let (id_pattern, template) = site
+        .listing_url
+        .as_ref()
+        .ok_or(format!("{name} listings can't be fetched by ID"))?;
+    if !id_pattern.is_match(id) {
+        return Err(format!("{id} is not a {name} listing ID"));
+    }
+    Ok(template.replace("{id}", id))
+}
+
+/// The site a listing URL belongs to, falling back to the generic extractor
+pub fn for_url(url: &str) -> &'static Site {
+    SITES
+        .iter()
+        .find(|site| site.hosts.iter().any(|host| url.contains(host)))
+        .unwrap_or(&GENERIC)
+}
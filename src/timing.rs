@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::{json, logging};
+
+/// Whether `--trace-timing` asked for the timings to be kept
+static TRACE: AtomicBool = AtomicBool::new(false);
+static TIMINGS: Mutex<Vec<Timing>> = Mutex::new(Vec::new());
+
+/// Where the time of one download went, in seconds since it started, as
+/// curl reports them
+#[derive(Debug, Clone)]
+pub struct Timing {
+    pub url: String,
+    pub dns: f64,
+    pub connect: f64,
+    /// Until the first byte of the response
+    pub first_byte: f64,
+    pub total: f64,
+}
+
+/// The curl `-w` variables `parse` expects, on one line
+pub const CURL_FORMAT: &str =
+    "%{time_namelookup} %{time_connect} %{time_starttransfer} %{time_total}";
+
+pub fn enable() {
+    TRACE.store(true, Ordering::SeqCst);
+}
+
+/// The DNS, connect, first byte and total times of a `CURL_FORMAT` line
+pub fn parse(line: &str) -> Option<[f64; 4]> {
+    line.split_whitespace()
+        .filter_map(|t| t.parse().ok())
+        .collect::<Vec<f64>>()
+        .try_into()
+        .ok()
+}
+
+/// Keeps the timing of a download of `url` from the line curl wrote for
+/// `CURL_FORMAT`, when tracing
+pub fn record(url: &str, line: &str) {
+    if !TRACE.load(Ordering::SeqCst) {
+        return;
+    }
+    let Some([dns, connect, first_byte, total]) = parse(line) else {
+        return;
+    };
+    TIMINGS.lock().unwrap().push(Timing {
+        url: url.to_string(),
+        dns,
+        connect,
+        first_byte,
+        total,
+    });
+}
+
+/// The value `p` of the way through the sorted `values`
+fn percentile(values: &[f64], p: f64) -> f64 {
+    let i = ((values.len() - 1) as f64 * p).round() as usize;
+    values[i]
+}
+
+/// Writes the timings kept so far to `path` as a JSON array and logs the
+/// p50 and p95 of the total and time to first byte. Does nothing unless
+/// tracing.
+pub fn save(path: &str) -> Result<(), Box<dyn Error>> {
+    if !TRACE.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let timings = TIMINGS.lock().unwrap().clone();
+    let seconds = |s: f64| format!("{s:.3}");
+    let objects = timings
+        .iter()
+        .map(|t| {
+            json::object(&[
+                ("url", json::string(&t.url)),
+                ("dns", seconds(t.dns)),
+                ("connect", seconds(t.connect)),
+                ("first_byte", seconds(t.first_byte)),
+                ("total", seconds(t.total)),
+            ])
+        })
+        .collect::<Vec<String>>();
+    fs::write(path, json::array(&objects) + "\n")?;
+
+    if timings.is_empty() {
+        return Ok(());
+    }
+    let sorted = |time: fn(&Timing) -> f64| {
+        let mut values = timings.iter().map(time).collect::<Vec<f64>>();
+        values.sort_by(f64::total_cmp);
+        values
+    };
+    let total = sorted(|t| t.total);
+    let first_byte = sorted(|t| t.first_byte);
+    let (p50, p95) = (percentile(&total, 0.5), percentile(&total, 0.95));
+    logging::info(
+        "timing",
+        &format!(
+            "{} download(s) took {p50:.2}s at p50 and {p95:.2}s at p95, first byte after {:.2}s and {:.2}s",
+            timings.len(),
+            percentile(&first_byte, 0.5),
+            percentile(&first_byte, 0.95)
+        ),
+        &[
+            ("downloads", &timings.len()),
+            ("total_p50", &seconds(p50)),
+            ("total_p95", &seconds(p95)),
+        ],
+    );
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+
+use crate::filetype;
+use crate::manifest::Manifest;
+
+/// Checks the files in `<output>/<name>/manifest.txt`: each one is there and
+/// not empty, an image's bytes match its extension and a hard link's original
+/// still exists. Returns what is wrong, one line per problem.
+pub fn verify(output: &str, name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let base_dir = format!("{output}/{name}");
+    let manifest_path = format!("{base_dir}/manifest.txt");
+    let manifest = Manifest::load(&manifest_path)
+        .map_err(|e| format!("Unable to load {manifest_path}: {e}"))?;
+
+    let mut problems = Vec::new();
+    for entry in &manifest.entries {
+        if entry.failed {
+            problems.push(format!(
+                "{}: the download failed, see --retry-failed",
+                entry.file
+            ));
+            continue;
+        }
+        if entry.file.is_empty() {
+            // Only hashed with --hash-only, there is no file to check
+            continue;
+        }
+        let path = format!("{base_dir}/{}", entry.file);
+        let mut head = [0u8; 16];
+        let read = match fs::File::open(&path).and_then(|mut file| file.read(&mut head)) {
+            Ok(0) => {
+                problems.push(format!("{}: empty file", entry.file));
+                continue;
+            }
+            Ok(read) => read,
+            Err(e) => {
+                problems.push(format!("{}: {e}", entry.file));
+                continue;
+            }
+        };
+
+        let extension = entry.file.rsplit_once('.').map_or("", |(_, ext)| ext);
+        if entry.file.starts_with("images/") {
+            match filetype::from_magic(&head[..read]) {
+                Some(kind) if kind == extension || (kind == "jpg" && extension == "jpeg") => {}
+                Some(kind) => problems.push(format!("{}: the file is a {kind}", entry.file)),
+                // svg and anything --force-extension set can't be told from the bytes
+                None => {}
+            }
+        }
+
+        if entry.integrity == "corrupt" {
+            problems.push(format!(
+                "{}: damaged or cut short when --validate-deep checked it",
+                entry.file
+            ));
+        }
+
+        if !entry.same_as.is_empty() && fs::metadata(format!("{output}/{}", entry.same_as)).is_err()
+        {
+            problems.push(format!(
+                "{}: the original {} is gone",
+                entry.file, entry.same_as
+            ));
+        }
+    }
+    Ok(problems)
+}